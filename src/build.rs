@@ -15,87 +15,752 @@
 // | Feel free to contact the author if you do come across this source code for some reason...                                         |
 // | <https://github.com/ethanbarry> is the author's profile.                                                                          |
 // + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
-
+use crate::chapter::Book;
+use crate::compat;
 use crate::config::*;
+use crate::driver::{self, Driver};
+use crate::log::*;
+use crate::math;
+use crate::message::*;
+use crate::presentation::Variant;
+use crate::thesis;
+use crate::{DocumentType, TexrsError};
 
-use colored::*;
+use std::collections::hash_map::DefaultHasher;
+use std::env;
 use std::error::Error;
+use std::ffi::OsString;
 use std::fs;
-use std::io::Read;
-use std::path::PathBuf;
-use std::process::Command;
-use toml;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+#[cfg(unix)]
+use std::sync::atomic::AtomicI32;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(unix)]
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+
+/* -------------------------------------------------------------------- */
+/// The directories a build works with. Sources are read from `tex/`,
+/// `bib/` & `graphics/`, or wherever the `[layout]` table puts them;
+/// every auxiliary & output file goes to `target/`. All paths are
+/// absolute, since the driver & biber run from different working
+/// directories.
+/* -------------------------------------------------------------------- */
+#[derive(Debug, Clone)]
+pub struct BuildDirs {
+    pub root: PathBuf,
+    pub tex: PathBuf,
+    pub bib: PathBuf,
+    pub graphics: PathBuf,
+    pub target: PathBuf,
+}
+
+impl BuildDirs {
+    /// Lays out the build directories under the project root `root`,
+    /// which must exist, as the config's `[layout]` says.
+    pub fn new(root: &Path, config: &ProjectConfig) -> Result<BuildDirs, Box<dyn Error>> {
+        let root = root.canonicalize()?;
+        let layout = config.get_layout();
+        Ok(BuildDirs {
+            tex: root.join(layout.get_tex()),
+            bib: root.join(layout.get_bib()),
+            graphics: root.join(layout.get_graphics()),
+            target: root.join("target"),
+            root,
+        })
+    }
+
+    /// A file in `target/` named after the job, e.g. `paper.log`.
+    pub fn output(&self, jobname: &str, extension: &str) -> PathBuf {
+        self.target.join(format!("{}.{}", jobname, extension))
+    }
+
+    /// `target/texrs/`, where texrs writes the sources it generates, such
+    /// as the wrappers for presentation variants.
+    pub fn generated(&self) -> PathBuf {
+        self.target.join("texrs")
+    }
+
+    /// Lets `\input` & `\includegraphics` find files in `tex/` & `graphics/`.
+    pub fn texinputs(&self) -> Result<OsString, Box<dyn Error>> {
+        search_path(&[&self.tex, &self.graphics], "TEXINPUTS")
+    }
+
+    /// Lets `\addbibresource` & biber find files in `bib/`.
+    pub fn bibinputs(&self) -> Result<OsString, Box<dyn Error>> {
+        search_path(&[&self.bib], "BIBINPUTS")
+    }
+}
+
+/// Builds a kpathsea search path of `dirs` followed by whatever the user
+/// already has in `var`. The trailing empty entry keeps the default
+/// TeX search path.
+fn search_path(dirs: &[&PathBuf], var: &str) -> Result<OsString, Box<dyn Error>> {
+    let mut entries: Vec<PathBuf> = dirs.iter().map(|dir| dir.to_path_buf()).collect();
+    match env::var_os(var) {
+        Some(existing) => entries.extend(env::split_paths(&existing)),
+        None => entries.push(PathBuf::new()),
+    }
+    Ok(env::join_paths(entries)?)
+}
 
-pub fn read_config(path: PathBuf) -> Result<ProjectConfig, Box<dyn Error>> {
-    dbg!(&path);
-    // let file_string = fs::read_to_string(path).expect("Error at read_to_string().");
-    let mut file = fs::File::open(path.canonicalize().expect("Cannot canonicalize path."))
-        .expect("Should be valid, yes?");
-    let mut file_string = String::new();
-    file.read_to_string(&mut file_string)
-        .expect("Failed to read.");
+/// How often a running tool is checked for completion or cancellation.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Knobs for a single build. `cancel` may be set from another thread to
+/// stop the build; the running tool is killed, with any processes it
+/// started, & the build returns `TexrsError::Cancelled`.
+#[derive(Clone, Default)]
+pub struct BuildOptions {
+    pub format: MessageFormat,
+    pub cancel: Arc<AtomicBool>,
+    /// Build just this variant of a presentation, not every one in the
+    /// config.
+    pub variant: Option<Variant>,
+    /// Build just these chapters of a book or thesis, named as
+    /// `texrs chapter` names them.
+    pub only: Vec<String>,
+}
 
-    let config: ProjectConfig = toml::from_str(&file_string)?;
-    Ok(config)
+/// One run of the driver over the document, until it's stable. The
+/// jobname names everything the run writes to `target/`; the source is
+/// relative to `tex/`, or an absolute path to a generated wrapper.
+struct Job {
+    jobname: String,
+    source: String,
 }
 
-pub fn build_project(config: ProjectConfig) -> Result<(), Box<dyn Error>> {
+/* -------------------------------------------------------------------- */
+/// Builds the project at `root` described by `config`: the document, or
+/// for a presentation each variant in its config. The driver is rerun until
+/// the document is stable: after every pass the `.log` file is checked
+/// for rerun or biber requests, & the `.aux` file is compared against
+/// the previous pass. The loop stops once none of those fire, or after
+/// `max_passes` passes, whichever comes first. Progress is reported as
+/// events in the format given by `options`. A failed driver pass ends
+/// the build with `TexrsError::DriverFailed`, a failed biber or makeindex
+/// run with its own error, & a log with errors in it with
+/// `TexrsError::CompileFailed`, after the diagnostics have been reported. Returns the log of the first job built.
+/* -------------------------------------------------------------------- */
+pub fn build_project(
+    root: &Path,
+    config: ProjectConfig,
+    options: &BuildOptions,
+) -> Result<BuildLog, Box<dyn Error>> {
+    let format = options.format;
+    let dirs = BuildDirs::new(root, &config)?;
+    let driver = driver::from_config(&config);
+
+    // A driver that can't compile the preamble is worth a warning up
+    // front; the build itself will show how it fails.
+    compat::check_driver(
+        &config,
+        &compat::project_packages(&dirs, &config.get_name()),
+        true,
+        format,
+    )?;
+
     // If the /target dir doesn't exist, create it, else skip the step.
-    if fs::metadata(config.get_name() + "/target").is_err() {
-        fs::create_dir(config.get_name() + "/target")?;
-        println!("[  {}  ] Creating target dir.", "OK".green());
-    } else {
-        println!("[  {}  ] Target dir. exists; skipping!", "OK".green());
+    let created = fs::metadata(&dirs.target).is_err();
+    if created {
+        fs::create_dir(&dirs.target)?;
     }
+    emit(
+        format,
+        Event::TargetDir {
+            path: dirs.target.display().to_string(),
+            created,
+        },
+    );
 
-    let mut num_of_passes = 1;
-    if config.get_citations() {
-        num_of_passes += 1;
+    // Some doctypes have a file generated from the config.
+    match config.get_doctype() {
+        DocumentType::Thesis => {
+            thesis::write_metadata(&config, &dirs.tex)?;
+        }
+        DocumentType::MathArticle => {
+            math::write_setup(&config, &dirs.tex)?;
+        }
+        _ => {}
     }
-    if config.get_graphics() {
-        num_of_passes += 1;
+    // `\include` writes an `.aux` file beside each included file's path
+    // in `target/`, & the driver won't create the directories for it.
+    mirror_dirs(&dirs.tex, &dirs.target)?;
+
+    let jobs = jobs(&config, &dirs, options)?;
+    let mut first_log = None;
+    for job in &jobs {
+        if jobs.len() > 1 {
+            emit(
+                format,
+                Event::JobStarted {
+                    jobname: job.jobname.clone(),
+                },
+            );
+        }
+        let log = build_job(&config, &dirs, driver.as_ref(), job, options)?;
+        first_log.get_or_insert(log);
     }
+    Ok(first_log.unwrap_or_default())
+}
 
-    for i in 0..num_of_passes {
-        let mut tex_builder = Command::new(config.get_driver());
-        tex_builder
-            .args(&["../tex/".to_owned() + &config.get_name() + ".tex"])
-            .current_dir(config.get_name() + "/target");
+/// The jobs a build runs: just the document, some of its chapters, or a
+/// job per variant for a presentation, writing the wrappers they need.
+fn jobs(
+    config: &ProjectConfig,
+    dirs: &BuildDirs,
+    options: &BuildOptions,
+) -> Result<Vec<Job>, Box<dyn Error>> {
+    if !options.only.is_empty() {
+        return Ok(vec![only_job(config, dirs, options)?]);
+    }
 
-        let output = tex_builder.output()?;
-        if output.status.success() {
-            println!(
-                "[  {}  ] Ran {} on pass {}.",
-                "OK".green(),
-                config.get_driver().as_str().blue(),
-                i.to_string().as_str().blue()
-            );
-            if i == num_of_passes {
-                println!("[  {}  ] Document compiled successfully!", "OK".green());
+    let name = config.get_name();
+    let presentation = config.get_doctype() == DocumentType::Presentation;
+    let variants = match options.variant {
+        Some(variant) if presentation => vec![variant],
+        Some(_) => return Err(Box::new(TexrsError::InvalidChoice("--variant".to_owned()))),
+        None if presentation => config.get_presentation().get_variants(),
+        None => vec![Variant::Slides],
+    };
+
+    let mut jobs = Vec::new();
+    for variant in variants {
+        let jobname = variant.jobname(&name);
+        let source = match variant.setup() {
+            None => format!("{}.tex", name),
+            Some(setup) => {
+                let wrapper = dirs.generated().join(format!("{}.tex", jobname));
+                write_if_changed(
+                    &wrapper,
+                    &format!(
+                        "% Generated by texrs; rewritten on every build.\n{}\n\\input{{{}.tex}}\n",
+                        setup, name
+                    ),
+                )?;
+                wrapper.display().to_string()
             }
+        };
+        jobs.push(Job { jobname, source });
+    }
+    Ok(jobs)
+}
+
+/* -------------------------------------------------------------------- */
+/// A job that builds only the chapters in `options.only`, through a
+/// wrapper that `\includeonly`s them & then `\input`s the main file, which
+/// is left untouched. The job keeps the document's jobname, so the
+/// `.aux` files of the chapters left out, from the last full build, are
+/// still read & references into them still resolve.
+/* -------------------------------------------------------------------- */
+fn only_job(
+    config: &ProjectConfig,
+    dirs: &BuildDirs,
+    options: &BuildOptions,
+) -> Result<Job, Box<dyn Error>> {
+    let name = config.get_name();
+    let book = Book::open(&dirs.root, config)?;
+    let mut chapters = Vec::new();
+    for query in &options.only {
+        chapters.push(book.chapters[book.find(query)?].include_path());
+    }
+
+    if fs::metadata(dirs.output(&name, "aux")).is_err() {
+        emit(
+            options.format,
+            Event::Warning {
+                message: "There's no full build yet, so references to other chapters won't \
+                          resolve; run `texrs build` first."
+                    .to_owned(),
+            },
+        );
+    }
+
+    let wrapper = dirs.generated().join(format!("{}.tex", name));
+    write_if_changed(
+        &wrapper,
+        &format!(
+            "% Generated by texrs; rewritten on every build.\n\\includeonly{{{}}}\n\\input{{{}.tex}}\n",
+            chapters.join(","),
+            name
+        ),
+    )?;
+    Ok(Job {
+        jobname: name,
+        source: wrapper.display().to_string(),
+    })
+}
+
+/// Runs one job to a stable document, reporting its diagnostics & PDF.
+fn build_job(
+    config: &ProjectConfig,
+    dirs: &BuildDirs,
+    driver: &dyn Driver,
+    job: &Job,
+    options: &BuildOptions,
+) -> Result<BuildLog, Box<dyn Error>> {
+    let format = options.format;
+    let jobname = &job.jobname;
+    let log_path = dirs.output(jobname, "log");
+    let aux_path = dirs.output(jobname, "aux");
+    let idx_path = dirs.output(jobname, "idx");
+    let pdf_path = dirs.output(jobname, "pdf");
+    // Drivers that rerun themselves only need to be started once.
+    let max_passes = if driver.handles_reruns() {
+        1
+    } else {
+        config.get_max_passes()
+    };
+    let mut aux_hash = hash_file(&aux_path);
+    let mut idx_hash = None;
+    let mut log = BuildLog::default();
+    let mut passes = 0;
+    let mut stable = false;
+    let mut driver_succeeded = false;
+    let mut failure = None;
+
+    for pass in 1..=max_passes {
+        passes = pass;
+        emit(
+            format,
+            Event::PassStarted {
+                pass,
+                driver: driver.name().to_owned(),
+            },
+        );
+
+        // The driver runs from `tex/` so relative `\input`s resolve as they
+        // would in an editor, but writes everything to `target/`.
+        let mut tex_builder =
+            driver.command(dirs, &job.source, &driver_arguments(config, driver, pass))?;
+
+        let output = run_tool(&mut tex_builder, &options.cancel)?;
+        // The driver echoes its log to stdout, which stands in if the log is missing.
+        log = BuildLog::read(&log_path)
+            .unwrap_or_else(|| BuildLog::parse(&String::from_utf8_lossy(&output.stdout)));
+        log.resolve(&dirs.tex);
+        driver_succeeded = output.status.success();
+        emit(
+            format,
+            Event::PassFinished {
+                pass,
+                driver: driver.name().to_owned(),
+                success: driver_succeeded,
+            },
+        );
+        if !driver_succeeded {
+            // A failed pass won't get better by rerunning it.
+            failure = Some(TexrsError::DriverFailed {
+                driver: driver.name().to_owned(),
+                code: output.status.code(),
+            });
+            break;
+        }
+        if driver.handles_reruns() {
+            stable = true;
+            break;
         }
 
-        if i == 1 && config.get_citations() {
-            let mut biber = Command::new("biber");
-            biber
-                .args(&[config.get_name()])
-                .current_dir(config.get_name() + "/tex");
-            let biber_output = biber.output()?;
-            if biber_output.status.success() {
-                println!(
-                    "[  {}  ] Ran biber on pass {}.",
-                    "OK".green(),
-                    i.to_string().as_str().blue()
-                );
-            } else {
-                println!(
-                    "[ {} ] Biber failed with the following error:",
-                    "FAIL".red()
-                );
-                eprintln!("{}", String::from_utf8_lossy(&biber_output.stderr));
+        let mut rerun = log.rerun_requested;
+
+        if log.biber_requested && !driver.handles_bibliography() {
+            if let Some(err) = run_biber(config, dirs, jobname, pass, options)? {
+                // Rerunning the driver won't mend the bibliography, but this
+                // pass's diagnostics & PDF are still worth reporting.
+                failure = Some(err);
+                break;
+            }
+            rerun = true;
+        }
+
+        // An index only needs rebuilding when its `.idx` file changed.
+        let new_idx_hash = hash_file(&idx_path);
+        if new_idx_hash.is_some() && new_idx_hash != idx_hash {
+            if let Some(err) = run_makeindex(config, dirs, jobname, pass, options)? {
+                failure = Some(err);
+                break;
+            }
+            rerun = true;
+        }
+        idx_hash = new_idx_hash;
+
+        let new_aux_hash = hash_file(&aux_path);
+        if new_aux_hash != aux_hash {
+            rerun = true;
+        }
+        aux_hash = new_aux_hash;
+
+        if !rerun {
+            stable = true;
+            break;
+        }
+    }
+
+    for diagnostic in &log.diagnostics {
+        emit(format, Event::Diagnostic(diagnostic.clone()));
+    }
+    if driver_succeeded && fs::metadata(&pdf_path).is_ok() {
+        emit(
+            format,
+            Event::Artifact {
+                path: pdf_path.display().to_string(),
+            },
+        );
+    }
+    emit(
+        format,
+        Event::build_finished(&log, passes, stable, failure.is_none()),
+    );
+
+    // The driver may exit cleanly even though its log has errors in it;
+    // the exit code has to agree with the `success` just reported.
+    match failure {
+        Some(err) => Err(Box::new(err)),
+        None if log.has_errors() => Err(Box::new(TexrsError::CompileFailed(log.errors().count()))),
+        None => Ok(log),
+    }
+}
+
+/// Runs biber on the `.bcf` file the driver left in `target/`, writing
+/// the `.bbl` back next to it. A biber failure is returned rather than
+/// raised, so the job can still report what it has.
+fn run_biber(
+    config: &ProjectConfig,
+    dirs: &BuildDirs,
+    jobname: &str,
+    pass: u32,
+    options: &BuildOptions,
+) -> Result<Option<TexrsError>, Box<dyn Error>> {
+    let mut biber = Command::new("biber");
+    biber
+        .args(config.get_tools().get_biber())
+        .arg("--input-directory")
+        .arg(&dirs.target)
+        .arg("--output-directory")
+        .arg(&dirs.target)
+        .arg(jobname)
+        .current_dir(&dirs.target)
+        .env("BIBINPUTS", dirs.bibinputs()?);
+    let biber_output = run_tool(&mut biber, &options.cancel)?;
+    let success = biber_output.status.success();
+
+    // Biber reports most of its errors on stdout, as `ERROR - ...` lines.
+    let mut stderr = String::from_utf8_lossy(&biber_output.stderr).into_owned();
+    if !success && stderr.trim().is_empty() {
+        stderr = String::from_utf8_lossy(&biber_output.stdout)
+            .lines()
+            .filter(|line| line.starts_with("ERROR"))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    emit(
+        options.format,
+        Event::BiberRun {
+            pass,
+            success,
+            stderr: stderr.clone(),
+        },
+    );
+    if success {
+        Ok(None)
+    } else {
+        Ok(Some(TexrsError::BiberFailed(stderr)))
+    }
+}
+
+/// Runs makeindex on the `.idx` file in `target/`. Like a biber failure,
+/// a makeindex failure is returned rather than raised.
+fn run_makeindex(
+    config: &ProjectConfig,
+    dirs: &BuildDirs,
+    jobname: &str,
+    pass: u32,
+    options: &BuildOptions,
+) -> Result<Option<TexrsError>, Box<dyn Error>> {
+    let mut makeindex = Command::new("makeindex");
+    makeindex
+        .args(config.get_tools().get_makeindex())
+        .arg(format!("{}.idx", jobname))
+        .current_dir(&dirs.target);
+    let makeindex_output = run_tool(&mut makeindex, &options.cancel)?;
+    let success = makeindex_output.status.success();
+    let stderr = String::from_utf8_lossy(&makeindex_output.stderr).into_owned();
+    emit(
+        options.format,
+        Event::MakeindexRun {
+            pass,
+            success,
+            stderr: stderr.clone(),
+        },
+    );
+    if success {
+        Ok(None)
+    } else {
+        Ok(Some(TexrsError::MakeindexFailed(stderr)))
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// Works out the driver arguments for pass `pass`: the `[tools]` override
+/// for that pass, else `[tools] driver`, else the top-level `arguments`,
+/// after any of the driver's default arguments whose flag isn't set yet,
+/// whether as `-flag=value` or as `-flag value`.
+/* -------------------------------------------------------------------- */
+pub fn driver_arguments(config: &ProjectConfig, driver: &dyn Driver, pass: u32) -> Vec<String> {
+    let tools = config.get_tools();
+    let mut arguments = tools
+        .get_pass(pass)
+        .or_else(|| tools.get_driver())
+        .or_else(|| config.get_arguments())
+        .unwrap_or_default();
+
+    if tools.get_defaults() {
+        for default in driver.default_arguments().iter().rev() {
+            let flag = flag_name(default);
+            if !arguments.iter().any(|arg| flag_name(arg) == flag) {
+                arguments.insert(0, default.to_string());
             }
         }
     }
+    arguments
+}
+
+/// `--interaction=batchmode`, `-interaction batchmode` &
+/// `-interaction=nonstopmode` all set the same flag. An argument that
+/// doesn't start with `-` is a value or a file, & names no flag.
+fn flag_name(argument: &str) -> Option<&str> {
+    let argument = argument.strip_prefix('-')?;
+    let argument = argument.trim_start_matches('-');
+    argument.split('=').next()
+}
 
+/// Creates a directory in `to` for each directory below `from`. `to`
+/// itself is skipped when it lies inside `from`, as `target/` does when
+/// the sources live at the project root.
+fn mirror_dirs(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir()
+            && !entry.file_name().to_string_lossy().starts_with('.')
+            && entry.path() != to
+        {
+            let mirror = to.join(entry.file_name());
+            fs::create_dir_all(&mirror)?;
+            mirror_dirs(&entry.path(), &mirror)?;
+        }
+    }
     Ok(())
 }
+
+/// Writes `contents` to `path`, creating its parent directories, unless
+/// the file already holds exactly that. An unchanged file keeps its
+/// modification time, so `watch` doesn't see it as an edit. Returns
+/// whether the file was written.
+pub fn write_if_changed(path: &Path, contents: &str) -> io::Result<bool> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    Ok(true)
+}
+
+/// The process group of the tool running now. Tools run in a group of
+/// their own, so that latexmk's or tectonic's own sub-processes can be
+/// killed with them, but then the terminal's Ctrl-C no longer reaches
+/// them; `interrupted` takes the group down with texrs instead.
+#[cfg(unix)]
+static TOOL_GROUP: AtomicI32 = AtomicI32::new(0);
+
+#[cfg(unix)]
+static INTERRUPT_HANDLER: Once = Once::new();
+
+#[cfg(unix)]
+extern "C" fn interrupted(signal: libc::c_int) {
+    let group = TOOL_GROUP.load(Ordering::SeqCst);
+    // Only async-signal-safe calls are allowed here.
+    unsafe {
+        if group > 0 {
+            libc::killpg(group, libc::SIGKILL);
+        }
+        libc::_exit(128 + signal);
+    }
+}
+
+/// Kills a tool along with any processes it started.
+fn kill_tool(child: &mut Child) {
+    #[cfg(unix)]
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/* -------------------------------------------------------------------- */
+/// Runs a tool to completion like `Command::output`, but kills it, & any
+/// processes it started, & returns `TexrsError::Cancelled` as soon as
+/// `cancel` is set. A tool that can't be found is a
+/// `TexrsError::DriverNotInstalled`.
+/* -------------------------------------------------------------------- */
+fn run_tool(command: &mut Command, cancel: &AtomicBool) -> Result<Output, Box<dyn Error>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+        INTERRUPT_HANDLER.call_once(|| {
+            let handler = interrupted as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+                unsafe {
+                    libc::signal(signal, handler);
+                }
+            }
+        });
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => {
+                TexrsError::DriverNotInstalled(command.get_program().to_string_lossy().into_owned())
+            }
+            _ => TexrsError::IoError(err),
+        })?;
+    #[cfg(unix)]
+    TOOL_GROUP.store(child.id() as i32, Ordering::SeqCst);
+
+    // Drain both pipes on their own threads so a chatty tool can't block.
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+    let stdout_reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stdout.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stderr.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+
+    let status = loop {
+        if cancel.load(Ordering::SeqCst) {
+            kill_tool(&mut child);
+            break None;
+        }
+        match child.try_wait()? {
+            Some(status) => break Some(status),
+            None => thread::sleep(CANCEL_POLL_INTERVAL),
+        }
+    };
+    #[cfg(unix)]
+    let _ = TOOL_GROUP.compare_exchange(child.id() as i32, 0, Ordering::SeqCst, Ordering::SeqCst);
+    let Some(status) = status else {
+        return Err(Box::new(TexrsError::Cancelled));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// Hashes a file's contents, or returns `None` if it can't be read.
+fn hash_file(path: &Path) -> Option<u64> {
+    let bytes = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(driver: &str, arguments: &[&str]) -> Vec<String> {
+        let mut config = ProjectConfig::new();
+        config.set_driver(driver);
+        config.set_arguments(Some(arguments.iter().map(|a| a.to_string()).collect()));
+        driver_arguments(&config, driver::from_config(&config).as_ref(), 1)
+    }
+
+    #[test]
+    fn defaults_fill_in_unset_flags() {
+        assert_eq!(
+            arguments("pdflatex", &[]),
+            [
+                "-interaction=nonstopmode",
+                "-halt-on-error",
+                "-file-line-error"
+            ]
+        );
+        assert_eq!(
+            arguments("pdflatex", &["--interaction=batchmode", "-shell-escape"]),
+            [
+                "-halt-on-error",
+                "-file-line-error",
+                "--interaction=batchmode",
+                "-shell-escape"
+            ]
+        );
+    }
+
+    #[test]
+    fn separate_flag_values() {
+        assert_eq!(
+            arguments("pdflatex", &["-interaction", "batchmode"]),
+            [
+                "-halt-on-error",
+                "-file-line-error",
+                "-interaction",
+                "batchmode"
+            ]
+        );
+        // A value named like a default flag doesn't stand in for it.
+        assert_eq!(
+            arguments("latexmk", &["-jobname", "pdf"]),
+            [
+                "-pdf",
+                "-interaction=nonstopmode",
+                "-halt-on-error",
+                "-file-line-error",
+                "-jobname",
+                "pdf"
+            ]
+        );
+    }
+
+    #[test]
+    fn tools_without_defaults() {
+        let mut config = ProjectConfig::new();
+        config.set_driver("pdflatex");
+        let mut tools = ToolArguments::default();
+        tools.set_defaults(Some(false));
+        tools.set_driver(Some(vec!["-draftmode".to_owned()]));
+        config.set_tools(Some(tools));
+        let driver = driver::from_config(&config);
+        assert_eq!(
+            driver_arguments(&config, driver.as_ref(), 1),
+            ["-draftmode"]
+        );
+    }
+}
@@ -0,0 +1,500 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::config::*;
+use crate::template::escape_tex;
+use crate::TexrsError;
+
+use colored::*;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The lines of the main file between these markers belong to texrs: one
+/// `\include` per chapter, in order.
+pub const BEGIN_MARKER: &str = "% texrs:chapters:begin";
+pub const END_MARKER: &str = "% texrs:chapters:end";
+
+/// Where chapters live, relative to `tex/`.
+pub const CHAPTERS_DIR: &str = "chapters";
+
+/* -------------------------------------------------------------------- */
+/// A chapter, kept in `tex/chapters/NN-slug.tex`. `number` is `None` for
+/// a file that isn't numbered yet, `chapters/slug.tex`.
+/* -------------------------------------------------------------------- */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub number: Option<usize>,
+    pub slug: String,
+}
+
+impl Chapter {
+    /// The file name without its extension, e.g. `03-background`.
+    pub fn stem(&self) -> String {
+        match self.number {
+            Some(number) => format!("{:02}-{}", number, self.slug),
+            None => self.slug.clone(),
+        }
+    }
+
+    /// The path `\include` & `\includeonly` take, e.g.
+    /// `chapters/03-background`.
+    pub fn include_path(&self) -> String {
+        format!("{}/{}", CHAPTERS_DIR, self.stem())
+    }
+
+    /// The chapter's file, under `tex`.
+    pub fn file(&self, tex: &Path) -> PathBuf {
+        tex.join(CHAPTERS_DIR).join(self.stem() + ".tex")
+    }
+
+    /// Reads a chapter from the path in its `\include`.
+    fn from_include(path: &str) -> Chapter {
+        let stem = path.rsplit('/').next().unwrap_or(path);
+        let stem = stem.strip_suffix(".tex").unwrap_or(stem);
+        match stem.split_once('-') {
+            Some((number, slug))
+                if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                Chapter {
+                    number: number.parse().ok(),
+                    slug: slug.to_owned(),
+                }
+            }
+            _ => Chapter {
+                number: None,
+                slug: stem.to_owned(),
+            },
+        }
+    }
+
+    /// Whether `query` names the chapter, by number (`3` or `03`), by slug
+    /// (`background`) or by file name (`03-background`).
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.strip_suffix(".tex").unwrap_or(query);
+        let query = query.rsplit('/').next().unwrap_or(query);
+        query == self.slug
+            || query == self.stem()
+            || query
+                .parse::<usize>()
+                .is_ok_and(|number| Some(number) == self.number)
+    }
+
+    /// The title in the chapter's `\chapter{...}`, if it has one.
+    pub fn title(&self, tex: &Path) -> Option<String> {
+        let source = fs::read_to_string(self.file(tex)).ok()?;
+        let start = source.find("\\chapter")?;
+        let rest = &source[start..];
+        let open = rest.find('{')?;
+        let close = rest[open..].find('}')?;
+        Some(rest[open + 1..open + close].to_owned())
+    }
+}
+
+/// Turns a title into a slug: lowercase letters & digits, with a single
+/// dash wherever anything else was.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "chapter".to_owned()
+    } else {
+        slug.to_owned()
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// The chapters of a multi-file project, as listed between the markers
+/// in its main file. Changes are made to `chapters`, then `save` renames
+/// the files to match their new numbers & rewrites the `\include`s.
+///
+/// ## Usage
+///
+/// ```rust
+/// let mut book = Book::open(&root, &config)?;
+/// book.chapters.swap(0, 1);
+/// book.save()?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub struct Book {
+    pub tex: PathBuf,
+    pub main: PathBuf,
+    pub chapters: Vec<Chapter>,
+    lines: Vec<String>,
+    begin: usize,
+    end: usize,
+}
+
+impl Book {
+    pub fn open(root: &Path, config: &ProjectConfig) -> Result<Book, Box<dyn Error>> {
+        let tex = root.join(config.get_layout().get_tex());
+        let main = tex.join(config.get_name() + ".tex");
+        let source = fs::read_to_string(&main)?;
+        let lines: Vec<String> = source.lines().map(str::to_owned).collect();
+        let marker = |marker: &str| lines.iter().position(|line| line.trim() == marker);
+        let (begin, end) = match (marker(BEGIN_MARKER), marker(END_MARKER)) {
+            (Some(begin), Some(end)) if begin < end => (begin, end),
+            _ => {
+                return Err(Box::new(TexrsError::ChapterError(format!(
+                    "`{}` has no `{}` & `{}` lines around its chapters.",
+                    main.display(),
+                    BEGIN_MARKER,
+                    END_MARKER
+                ))))
+            }
+        };
+        let chapters = lines[begin + 1..end]
+            .iter()
+            .filter_map(|line| include_argument(line))
+            .map(Chapter::from_include)
+            .collect();
+        Ok(Book {
+            tex,
+            main,
+            chapters,
+            lines,
+            begin,
+            end,
+        })
+    }
+
+    /// The position of the chapter `query` names.
+    pub fn find(&self, query: &str) -> Result<usize, TexrsError> {
+        self.chapters
+            .iter()
+            .position(|chapter| chapter.matches(query))
+            .ok_or_else(|| TexrsError::ChapterError(format!("no chapter `{}`.", query)))
+    }
+
+    /// Renumbers the chapter files to match their order, then rewrites
+    /// the `\include`s between the markers.
+    pub fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        let numbered: Vec<Chapter> = self
+            .chapters
+            .iter()
+            .enumerate()
+            .map(|(i, chapter)| Chapter {
+                number: Some(i + 1),
+                slug: chapter.slug.clone(),
+            })
+            .collect();
+
+        // Renaming in two steps keeps swapped chapters from overwriting
+        // each other.
+        let mut moves = Vec::new();
+        for (i, (old, new)) in self.chapters.iter().zip(&numbered).enumerate() {
+            let from = old.file(&self.tex);
+            if old != new && from.exists() {
+                let staging = self
+                    .tex
+                    .join(CHAPTERS_DIR)
+                    .join(format!(".texrs-renumber-{}.tex", i));
+                fs::rename(&from, &staging)?;
+                moves.push((staging, new.file(&self.tex)));
+            }
+        }
+        for (staging, to) in moves {
+            if to.exists() {
+                return Err(Box::new(TexrsError::ChapterError(format!(
+                    "`{}` is in the way; the renumbered chapter is in `{}`.",
+                    to.display(),
+                    staging.display()
+                ))));
+            }
+            fs::rename(&staging, &to)?;
+        }
+
+        let indent: String = self.lines[self.begin]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let includes: Vec<String> = numbered
+            .iter()
+            .map(|chapter| format!("{}\\include{{{}}}", indent, chapter.include_path()))
+            .collect();
+        self.lines.splice(self.begin + 1..self.end, includes);
+        self.end = self.begin + 1 + numbered.len();
+        self.chapters = numbered;
+        fs::write(&self.main, self.lines.join("\n") + "\n")?;
+        Ok(())
+    }
+}
+
+/// The argument of an `\include{...}` line, if the line is one.
+fn include_argument(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix("\\include{")?;
+    Some(&rest[..rest.find('}')?])
+}
+
+/// Prints the chapters in order, with their titles.
+pub fn list_chapters(book: &Book) {
+    if book.chapters.is_empty() {
+        println!("[ {} ] No chapters yet.", "INFO".blue());
+    }
+    for chapter in &book.chapters {
+        println!(
+            "{:>3}  {:<32} {}",
+            chapter.number.map(|n| n.to_string()).unwrap_or_default(),
+            chapter.stem(),
+            chapter.title(&book.tex).unwrap_or_default()
+        );
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// Adds a chapter called `title` at position `at`, counting from 1, or at
+/// the end. Its file starts with a `\chapter` & a `\label`; a file left
+/// behind by `remove --keep-file` is taken back as it is.
+/* -------------------------------------------------------------------- */
+pub fn add_chapter(book: &mut Book, title: &str, at: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let slug = slugify(title);
+    if book.chapters.iter().any(|chapter| chapter.slug == slug) {
+        return Err(Box::new(TexrsError::ChapterError(format!(
+            "there's already a chapter `{}`.",
+            slug
+        ))));
+    }
+    let chapter = Chapter { number: None, slug };
+    let file = chapter.file(&book.tex);
+    if !file.exists() {
+        fs::create_dir_all(book.tex.join(CHAPTERS_DIR))?;
+        fs::write(
+            &file,
+            format!(
+                "\\chapter{{{}}}\n\\label{{chap:{}}}\n\n",
+                escape_tex(title),
+                chapter.slug
+            ),
+        )?;
+    }
+    let position = at
+        .map(|at| at.clamp(1, book.chapters.len() + 1) - 1)
+        .unwrap_or(book.chapters.len());
+    book.chapters.insert(position, chapter);
+    book.save()?;
+    println!(
+        "[  {}  ] Added {}.",
+        "OK".green(),
+        book.chapters[position].file(&book.tex).display()
+    );
+    Ok(())
+}
+
+/// Removes a chapter & deletes its file, or with `keep_file` leaves the
+/// file behind, unnumbered, as `chapters/slug.tex`.
+pub fn remove_chapter(book: &mut Book, query: &str, keep_file: bool) -> Result<(), Box<dyn Error>> {
+    let chapter = book.chapters.remove(book.find(query)?);
+    let file = chapter.file(&book.tex);
+    if keep_file {
+        let kept = Chapter {
+            number: None,
+            ..chapter
+        }
+        .file(&book.tex);
+        if file.exists() {
+            fs::rename(&file, &kept)?;
+        }
+        println!("[  {}  ] Kept {}.", "OK".green(), kept.display());
+    } else if file.exists() {
+        fs::remove_file(&file)?;
+        println!("[  {}  ] Removed {}.", "OK".green(), file.display());
+    }
+    book.save()
+}
+
+/// Moves a chapter to `position`, counting from 1.
+pub fn move_chapter(book: &mut Book, query: &str, position: usize) -> Result<(), Box<dyn Error>> {
+    let chapter = book.chapters.remove(book.find(query)?);
+    let position = position.clamp(1, book.chapters.len() + 1) - 1;
+    book.chapters.insert(position, chapter);
+    book.save()?;
+    println!(
+        "[  {}  ] Moved {} to position {}.",
+        "OK".green(),
+        book.chapters[position].slug,
+        position + 1
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    /// A project directory of its own for each test, removed afterwards.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Scratch {
+            let dir = env::temp_dir().join(format!("texrs-chapter-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("tex").join(CHAPTERS_DIR)).unwrap();
+            Scratch(dir)
+        }
+
+        fn tex(&self) -> PathBuf {
+            self.0.join("tex")
+        }
+
+        /// A book whose main file includes `stems` in order, each with a
+        /// file whose `\chapter` is its stem.
+        fn book(&self, stems: &[&str]) -> Book {
+            let mut main = String::from("\\begin{document}\n  % texrs:chapters:begin\n");
+            for stem in stems {
+                main.push_str(&format!("  \\include{{chapters/{}}}\n", stem));
+                fs::write(self.chapter(stem), format!("\\chapter{{{}}}\n", stem)).unwrap();
+            }
+            main.push_str("  % texrs:chapters:end\n\\end{document}\n");
+            fs::write(self.tex().join("book.tex"), main).unwrap();
+            let mut config = ProjectConfig::new();
+            config.set_name("book");
+            Book::open(&self.0, &config).unwrap()
+        }
+
+        fn chapter(&self, stem: &str) -> PathBuf {
+            self.tex().join(CHAPTERS_DIR).join(format!("{}.tex", stem))
+        }
+
+        fn main(&self) -> String {
+            fs::read_to_string(self.tex().join("book.tex")).unwrap()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn chapters_from_includes() {
+        let chapter = Chapter::from_include("chapters/03-related-work");
+        assert_eq!(chapter.number, Some(3));
+        assert_eq!(chapter.slug, "related-work");
+        for query in [
+            "3",
+            "03",
+            "related-work",
+            "03-related-work",
+            "chapters/03-related-work.tex",
+        ] {
+            assert!(chapter.matches(query), "{} didn't match", query);
+        }
+        assert!(!chapter.matches("4"));
+
+        let unnumbered = Chapter::from_include("chapters/appendix.tex");
+        assert_eq!(unnumbered.number, None);
+        assert_eq!(unnumbered.stem(), "appendix");
+        assert_eq!(Chapter::from_include("chapters/-x").number, None);
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Related Work: A Survey!"), "related-work-a-survey");
+        assert_eq!(slugify("  Über 9000  "), "ber-9000");
+        assert_eq!(slugify("???"), "chapter");
+    }
+
+    #[test]
+    fn missing_markers() {
+        let scratch = Scratch::new("markers");
+        fs::write(
+            scratch.tex().join("book.tex"),
+            "% texrs:chapters:end\n% texrs:chapters:begin\n",
+        )
+        .unwrap();
+        let mut config = ProjectConfig::new();
+        config.set_name("book");
+        assert!(matches!(
+            Book::open(&scratch.0, &config).map_err(|err| err.downcast::<TexrsError>()),
+            Err(Ok(err)) if matches!(*err, TexrsError::ChapterError(_))
+        ));
+    }
+
+    #[test]
+    fn moving_renumbers_files_and_includes() {
+        let scratch = Scratch::new("move");
+        let mut book = scratch.book(&["01-intro", "02-background", "03-results"]);
+        move_chapter(&mut book, "results", 1).unwrap();
+
+        assert_eq!(
+            scratch.main(),
+            "\\begin{document}\n  % texrs:chapters:begin\n  \\include{chapters/01-results}\n  \
+             \\include{chapters/02-intro}\n  \\include{chapters/03-background}\n  \
+             % texrs:chapters:end\n\\end{document}\n"
+        );
+        // Each file moved with its chapter, none overwriting another.
+        for (stem, contents) in [
+            ("01-results", "03-results"),
+            ("02-intro", "01-intro"),
+            ("03-background", "02-background"),
+        ] {
+            assert_eq!(
+                fs::read_to_string(scratch.chapter(stem)).unwrap(),
+                format!("\\chapter{{{}}}\n", contents)
+            );
+        }
+    }
+
+    #[test]
+    fn removing_with_keep_file_unnumbers_it() {
+        let scratch = Scratch::new("remove");
+        let mut book = scratch.book(&["01-intro", "02-background", "03-results"]);
+        remove_chapter(&mut book, "1", true).unwrap();
+
+        assert!(!scratch.chapter("01-intro").exists());
+        assert_eq!(
+            fs::read_to_string(scratch.chapter("intro")).unwrap(),
+            "\\chapter{01-intro}\n"
+        );
+        assert!(scratch.chapter("01-background").exists());
+        assert!(scratch.chapter("02-results").exists());
+        assert!(!scratch.chapter("03-results").exists());
+        assert!(scratch.main().contains(
+            "begin\n  \\include{chapters/01-background}\n  \\include{chapters/02-results}\n  %"
+        ));
+
+        // Adding it back takes the kept file as it is.
+        add_chapter(&mut book, "Intro", Some(2)).unwrap();
+        assert!(!scratch.chapter("intro").exists());
+        assert_eq!(
+            fs::read_to_string(scratch.chapter("02-intro")).unwrap(),
+            "\\chapter{01-intro}\n"
+        );
+        assert!(scratch.chapter("03-results").exists());
+    }
+
+    #[test]
+    fn adding_a_duplicate_is_refused() {
+        let scratch = Scratch::new("add");
+        let mut book = scratch.book(&["01-intro"]);
+        assert!(add_chapter(&mut book, "Intro!", None).is_err());
+        add_chapter(&mut book, "Methods & Data", None).unwrap();
+        assert_eq!(
+            fs::read_to_string(scratch.chapter("02-methods-data")).unwrap(),
+            "\\chapter{Methods \\& Data}\n\\label{chap:methods-data}\n\n"
+        );
+    }
+}
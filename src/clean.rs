@@ -0,0 +1,143 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use colored::*;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files in `target/` with these extensions are build leftovers.
+pub const AUX_EXTENSIONS: [&str; 24] = [
+    "aux",
+    "bbl",
+    "bcf",
+    "blg",
+    "run.xml",
+    "log",
+    "toc",
+    "out",
+    "synctex.gz",
+    "lof",
+    "lot",
+    "fls",
+    "fdb_latexmk",
+    "idx",
+    "ind",
+    "ilg",
+    "nav",
+    "snm",
+    "vrb",
+    "xdv",
+    "dvi",
+    "glo",
+    "gls",
+    "glg",
+];
+
+/// What `clean` should remove.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanOptions {
+    /// Leave the built PDFs alone.
+    pub keep_pdf: bool,
+    /// Only list what would be removed.
+    pub dry_run: bool,
+    /// Remove everything in `target/`, generated assets included.
+    pub all: bool,
+}
+
+/* -------------------------------------------------------------------- */
+/// Removes build artifacts from the `target/` directory of the project at
+/// `root`. By default that's every auxiliary file plus the PDFs; `all`
+/// widens it to everything in `target/`. Returns the removed paths.
+///
+/// ## Usage
+///
+/// ```rust
+/// clean_project(&root, CleanOptions { keep_pdf: true, ..Default::default() })?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn clean_project(root: &Path, options: CleanOptions) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let target = root.join("target");
+    if !target.is_dir() {
+        println!("[  {}  ] Nothing to clean.", "OK".green());
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    collect(&target, &mut files, &mut dirs)?;
+
+    let doomed: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            if has_extension(&name, "pdf") {
+                !options.keep_pdf
+            } else {
+                options.all || AUX_EXTENSIONS.iter().any(|ext| has_extension(&name, ext))
+            }
+        })
+        .collect();
+
+    for file in &doomed {
+        if options.dry_run {
+            println!("[ {} ] Would remove {}.", "INFO".blue(), file.display());
+        } else {
+            fs::remove_file(file)?;
+            println!("[  {}  ] Removed {}.", "OK".green(), file.display());
+        }
+    }
+
+    if !options.dry_run {
+        // Deepest first, so that emptied parents can go too. Directories
+        // that still hold something are left alone.
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        for dir in dirs {
+            let _ = fs::remove_dir(dir);
+        }
+        if options.all {
+            let _ = fs::remove_dir(&target);
+        }
+    }
+
+    Ok(doomed)
+}
+
+fn has_extension(name: &str, extension: &str) -> bool {
+    name.len() > extension.len() + 1
+        && name.ends_with(extension)
+        && name[..name.len() - extension.len()].ends_with('.')
+}
+
+/// Gathers every file & subdirectory below `dir`.
+fn collect(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+    dirs: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect(&path, files, dirs)?;
+            dirs.push(path);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -16,143 +16,240 @@
 // | <https://github.com/ethanbarry> is the author's profile.                                                                          |
 // + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
 
-use crate::{config::*, DocumentType, TexrsError};
+use crate::compat::{self, Severity};
+use crate::{config::*, new, registry::*, DocumentType, TexrsError};
+use clap::Args;
 use std::error::Error;
+use std::path::PathBuf;
 
-// TODO Merge these two fns. by taking an Option<String> and matching on that.
-pub fn config_menu(name: &str) -> Result<ProjectConfig, Box<dyn Error>> {
-    use cumaea::{Choice::*, ChoiceColor::*}; // Import enums from the cumaea crate.
+/// How a new project is set up: the answers to every prompt of the
+/// interactive menu, each of which can also be given as a flag. `new`
+/// fills in whatever's left out from the template & the user config;
+/// `interactive` prompts for it.
+#[derive(Debug, Default, Args)]
+pub struct NewOptions {
+    /// Template to start from: a built-in type such as `article` or
+    /// `math-article`, or the name of a user template.
+    #[arg(long, require_equals = true, value_name = "NAME")]
+    pub template: Option<String>,
+    /// Driver to build with, e.g. `pdflatex`, `lualatex` or `tectonic`.
+    #[arg(long)]
+    pub driver: Option<String>,
+    /// Set up a bibliography.
+    #[arg(long, overrides_with = "no_citations")]
+    pub citations: bool,
+    /// Don't set up a bibliography.
+    #[arg(long, overrides_with = "citations")]
+    pub no_citations: bool,
+    /// Set up a graphics directory.
+    #[arg(long, overrides_with = "no_graphics")]
+    pub graphics: bool,
+    /// Don't set up a graphics directory.
+    #[arg(long, overrides_with = "graphics")]
+    pub no_graphics: bool,
+    /// Directory to create the project in; defaults to its jobname.
+    #[arg(long, value_name = "DIR")]
+    pub path: Option<PathBuf>,
+    /// Don't make the project a git repository.
+    #[arg(long)]
+    pub no_git: bool,
+    /// Document title; defaults to the project name.
+    #[arg(long)]
+    pub title: Option<String>,
+    /// Document author.
+    #[arg(long)]
+    pub author: Option<String>,
+    /// Paper size, e.g. `a4paper`.
+    #[arg(long)]
+    pub paper: Option<String>,
+    /// Document language, as babel names it, e.g. `british`.
+    #[arg(long)]
+    pub language: Option<String>,
+}
 
-    let mut config = ProjectConfig::new();
-    config.set_name(name);
+impl NewOptions {
+    /// `Some(true)` for `--citations`, `Some(false)` for `--no-citations`.
+    pub fn get_citations(&self) -> Option<bool> {
+        flag(self.citations, self.no_citations)
+    }
 
-    // Prompt for driver:
-    match cumaea::prompt_selection(
-        "Select driver",
-        "(P)dflatex, (l)ualatex, (x)elatex",
-        Some(Normal(Green)),
-        "p",
-    )
-    .to_ascii_lowercase()
-    .as_str()
-    {
-        "p" => config.set_driver("pdflatex"),
-        "x" => config.set_driver("xelatex"),
-        "l" => config.set_driver("lualatex"),
-        _ => config.set_driver("pdflatex"),
+    /// `Some(true)` for `--graphics`, `Some(false)` for `--no-graphics`.
+    pub fn get_graphics(&self) -> Option<bool> {
+        flag(self.graphics, self.no_graphics)
     }
 
-    match cumaea::prompt_selection(
-        "Select document category",
-        "(M)athematical, (f)ormal, (p)ersonal",
-        Some(Normal(Green)),
-        "m",
-    )
-    .to_ascii_lowercase()
-    .as_str()
-    {
-        "m" => {
-            match cumaea::prompt_selection(
-                "Select document type",
-                "(N)otes, (b)ook, (m)athematical article, (p)resentation",
-                Some(Normal(Green)),
-                "n",
-            )
-            .to_ascii_lowercase()
-            .as_str()
-            {
-                "b" => config.set_doctype(DocumentType::Book),
-                "m" => config.set_doctype(DocumentType::MathArticle),
-                "n" => config.set_doctype(DocumentType::Notes),
-                "p" => config.set_doctype(DocumentType::Presentation),
-                incorrect => {
-                    return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
-                }
-            }
+    /// The directory the project goes in: `--path`, or else one named
+    /// after its jobname in the current directory.
+    pub fn root(&self, config: &ProjectConfig) -> PathBuf {
+        self.path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(config.get_name()))
+    }
+
+    /// Gives `config` everything these options set, leaving the rest.
+    pub fn apply(&self, config: &mut ProjectConfig) {
+        if let Some(driver) = &self.driver {
+            config.set_driver(driver);
         }
-        "f" => {
-            match cumaea::prompt_selection(
-                "Select document type",
-                "(A)rticle, (t)hesis",
-                Some(Normal(Green)),
-                "a",
-            )
-            .to_ascii_lowercase()
-            .as_str()
-            {
-                "a" => config.set_doctype(DocumentType::Article),
-                "t" => config.set_doctype(DocumentType::Thesis),
-                incorrect => {
-                    return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
-                }
-            }
+        if let Some(citations) = self.get_citations() {
+            config.set_citations(citations);
         }
-        "p" => {
-            match cumaea::prompt_selection(
-                "Select document type",
-                "(L)etter, (r)ecipe",
-                Some(Normal(Green)),
-                "l",
-            )
-            .to_ascii_lowercase()
-            .as_str()
-            {
-                "l" => config.set_doctype(DocumentType::Letter),
-                "r" => config.set_doctype(DocumentType::Recipe),
-                incorrect => {
-                    return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
-                }
-            }
+        if let Some(graphics) = self.get_graphics() {
+            config.set_graphics(graphics);
         }
-        incorrect => {
-            return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
+
+        let mut document = config.get_document();
+        if self.title.is_some() {
+            document.set_title(self.title.clone());
+        }
+        if self.author.is_some() {
+            document.set_author(self.author.clone());
+        }
+        if self.paper.is_some() {
+            document.set_paper(self.paper.clone());
+        }
+        if self.language.is_some() {
+            document.set_language(self.language.clone());
+        }
+        config.set_document(Some(document));
+
+        if self.no_git {
+            let mut vcs = config.get_vcs();
+            vcs.set_enabled(Some(false));
+            config.set_vcs(Some(vcs));
         }
     }
+}
 
-    // Prompt for citations:
-    if cumaea::prompt_tf_default("Include citations? (Y/n): ", None, true) {
-        config.set_citations(true);
-    } else {
-        config.set_citations(false);
+/// Names the project `name`, made a safe jobname. When that changes the
+/// name, the name as given is kept as the title.
+fn set_name(config: &mut ProjectConfig, name: &str) -> Result<(), TexrsError> {
+    let jobname = new::jobname(name)?;
+    if jobname != name {
+        let mut document = config.get_document();
+        if document.get_title().is_none() {
+            document.set_title(Some(name.trim().to_owned()));
+            config.set_document(Some(document));
+        }
     }
+    config.set_name(&jobname);
+    Ok(())
+}
 
-    // Prompt for graphics:
-    if cumaea::prompt_tf_default("Include graphics? (Y/n): ", None, true) {
-        config.set_graphics(true);
-    } else {
-        config.set_graphics(false);
+fn flag(yes: bool, no: bool) -> Option<bool> {
+    match (yes, no) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
     }
+}
 
+/* -------------------------------------------------------------------- */
+/// Makes the config for `texrs new`: the template's defaults, as the
+/// user config has them, with `options` on top. Never prompts.
+///
+/// ## Usage
+///
+/// ```rust
+/// let config = cli::options_config("paper", &options)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn options_config(name: &str, options: &NewOptions) -> Result<ProjectConfig, TexrsError> {
+    let mut config = ProjectConfig::new();
+    let template = options.template.as_deref().unwrap_or("article");
+    Registry::discover().find(template)?.configure(&mut config);
+    options.apply(&mut config);
+    set_name(&mut config, name)?;
     Ok(config)
 }
 
-pub fn config_menu_nameless() -> Result<ProjectConfig, Box<dyn Error>> {
+/* -------------------------------------------------------------------- */
+/// Makes the config for `texrs interactive`, prompting for whatever
+/// `name` & `options` leave out. A blank line takes the default shown,
+/// which comes from the template & the user config.
+///
+/// ## Usage
+///
+/// ```rust
+/// let config = cli::config_menu(None, &NewOptions::default())?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn config_menu(
+    name: Option<&str>,
+    options: &NewOptions,
+) -> Result<ProjectConfig, Box<dyn Error>> {
     use cumaea::{Choice::*, ChoiceColor::*}; // Import enums from the cumaea crate.
 
     let mut config = ProjectConfig::new();
 
     // Prompt for the name.
-    config.set_name(&cumaea::prompt_text("Enter a", "name", Some(Normal(Green))));
+    let name = match name {
+        Some(name) => name.to_owned(),
+        None => cumaea::prompt_text("Enter a", "name", Some(Normal(Green))),
+    };
+    set_name(&mut config, &name)?;
 
-    // Prompt for driver:
-    match cumaea::prompt_selection(
-        "Select driver",
-        "(P)dflatex, (l)ualatex, (x)elatex",
-        Some(Normal(Green)),
-        "p",
-    )
-    .to_ascii_lowercase()
-    .as_str()
-    {
-        "p" => config.set_driver("pdflatex"),
-        "x" => config.set_driver("xelatex"),
-        "l" => config.set_driver("lualatex"),
-        _ => config.set_driver("pdflatex"),
+    let registry = Registry::discover();
+    match &options.template {
+        Some(template) => registry.find(template)?.select(&mut config),
+        None => prompt_doctype(&registry, &mut config)?,
+    }
+
+    // The template's defaults, as the user config has them, are the
+    // answers a blank line gives from here on.
+    resolve(&config)?.configure(&mut config);
+    options.apply(&mut config);
+
+    if options.driver.is_none() {
+        prompt_driver(&mut config);
+    }
+
+    // Prompt for the details that fill in the template; blank skips.
+    let mut document = config.get_document();
+    if options.title.is_none() {
+        let title =
+            cumaea::prompt_text("Enter a", "title (blank for the name)", Some(Normal(Green)));
+        if !title.is_empty() {
+            document.set_title(Some(title));
+        }
     }
+    if options.author.is_none() {
+        let author_prompt = match document.get_author().as_str() {
+            "" => "author".to_owned(),
+            author => format!("author (blank for {})", author),
+        };
+        let author = cumaea::prompt_text("Enter the", &author_prompt, Some(Normal(Green)));
+        if !author.is_empty() {
+            document.set_author(Some(author));
+        }
+    }
+    config.set_document(Some(document));
+
+    if options.get_citations().is_none() {
+        config.set_citations(prompt_flag("Include citations?", config.get_citations()));
+    }
+    if options.get_graphics().is_none() {
+        config.set_graphics(prompt_flag("Include graphics?", config.get_graphics()));
+    }
+
+    Ok(config)
+}
 
-    match cumaea::prompt_selection(
+/// Prompts for the category & type of document, or a user template. A
+/// type is picked by its template's name, as `--template` would, so a
+/// user template named like a built-in one is used in its place.
+fn prompt_doctype(registry: &Registry, config: &mut ProjectConfig) -> Result<(), Box<dyn Error>> {
+    use cumaea::{Choice::*, ChoiceColor::*};
+
+    // User templates get a category of their own, when there are any.
+    let categories = if registry.user_templates().next().is_some() {
+        "(M)athematical, (f)ormal, (p)ersonal, (u)ser template"
+    } else {
+        "(M)athematical, (f)ormal, (p)ersonal"
+    };
+    let doctype = match cumaea::prompt_selection(
         "Select document category",
-        "(M)athematical, (f)ormal, (p)ersonal",
+        categories,
         Some(Normal(Green)),
         "m",
     )
@@ -169,10 +266,10 @@ pub fn config_menu_nameless() -> Result<ProjectConfig, Box<dyn Error>> {
             .to_ascii_lowercase()
             .as_str()
             {
-                "b" => config.set_doctype(DocumentType::Book),
-                "m" => config.set_doctype(DocumentType::MathArticle),
-                "n" => config.set_doctype(DocumentType::Notes),
-                "p" => config.set_doctype(DocumentType::Presentation),
+                "b" => DocumentType::Book,
+                "m" => DocumentType::MathArticle,
+                "n" => DocumentType::Notes,
+                "p" => DocumentType::Presentation,
                 incorrect => {
                     return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
                 }
@@ -188,8 +285,8 @@ pub fn config_menu_nameless() -> Result<ProjectConfig, Box<dyn Error>> {
             .to_ascii_lowercase()
             .as_str()
             {
-                "a" => config.set_doctype(DocumentType::Article),
-                "t" => config.set_doctype(DocumentType::Thesis),
+                "a" => DocumentType::Article,
+                "t" => DocumentType::Thesis,
                 incorrect => {
                     return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
                 }
@@ -205,31 +302,111 @@ pub fn config_menu_nameless() -> Result<ProjectConfig, Box<dyn Error>> {
             .to_ascii_lowercase()
             .as_str()
             {
-                "l" => config.set_doctype(DocumentType::Letter),
-                "r" => config.set_doctype(DocumentType::Recipe),
+                "l" => DocumentType::Letter,
+                "r" => DocumentType::Recipe,
                 incorrect => {
                     return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
                 }
             }
         }
+        "u" => return select_user_template(registry, config),
         incorrect => {
             return Err(Box::new(TexrsError::InvalidChoice(incorrect.to_owned())));
         }
-    }
+    };
+    registry.find(&builtin_name(doctype))?.select(config);
+    Ok(())
+}
 
-    // Prompt for citations:
-    if cumaea::prompt_tf_default("Include citations? (Y/n): ", None, true) {
-        config.set_citations(true);
-    } else {
-        config.set_citations(false);
+/// The drivers the menu offers, with the letter that picks each.
+const DRIVERS: [(&str, &str); 5] = [
+    ("p", "pdflatex"),
+    ("l", "lualatex"),
+    ("x", "xelatex"),
+    ("t", "tectonic"),
+    ("m", "latexmk"),
+];
+
+/// Prompts for the driver. A blank line keeps the one `config` has,
+/// whose letter is shown capitalized.
+fn prompt_driver(config: &mut ProjectConfig) {
+    use cumaea::{Choice::*, ChoiceColor::*};
+
+    let current = config.get_driver();
+    let default = DRIVERS
+        .iter()
+        .find(|(_, driver)| *driver == current)
+        .map(|(key, _)| *key)
+        .unwrap_or_default();
+    let options: Vec<String> = DRIVERS
+        .iter()
+        .map(|(key, driver)| {
+            let shown = if *key == default {
+                key.to_ascii_uppercase()
+            } else {
+                key.to_string()
+            };
+            driver.replacen(key, &format!("({})", shown), 1)
+        })
+        .collect();
+    let choice = cumaea::prompt_selection(
+        "Select driver",
+        &options.join(", "),
+        Some(Normal(Green)),
+        default,
+    )
+    .to_ascii_lowercase();
+    if let Some((_, driver)) = DRIVERS.iter().find(|(key, _)| *key == choice) {
+        config.set_driver(driver);
     }
+}
 
-    // Prompt for graphics:
-    if cumaea::prompt_tf_default("Include graphics? (Y/n): ", None, true) {
-        config.set_graphics(true);
-    } else {
-        config.set_graphics(false);
+/// Asks a yes-or-no `question`, where a blank line means `default`.
+fn prompt_flag(question: &str, default: bool) -> bool {
+    let hint = if default { "(Y/n)" } else { "(y/N)" };
+    cumaea::prompt_tf_default(&format!("{} {}: ", question, hint), None, default)
+}
+
+/// Prompts for one of the user templates by name & selects it.
+fn select_user_template(
+    registry: &Registry,
+    config: &mut ProjectConfig,
+) -> Result<(), Box<dyn Error>> {
+    use cumaea::{Choice::*, ChoiceColor::*};
+
+    let names: Vec<&str> = registry
+        .user_templates()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    let choice = cumaea::prompt_selection(
+        "Select template",
+        &names.join(", "),
+        Some(Normal(Green)),
+        names.first().copied().unwrap_or_default(),
+    );
+    match registry.user_templates().find(|entry| entry.name == choice) {
+        Some(entry) => {
+            entry.select(config);
+            Ok(())
+        }
+        None => Err(Box::new(TexrsError::InvalidChoice(choice))),
     }
+}
 
-    Ok(config)
+/// If the chosen driver can't compile the chosen template, offers to
+/// switch to one that can.
+pub fn confirm_driver(config: &mut ProjectConfig) -> Result<(), Box<dyn Error>> {
+    let files = resolve(config)?.render(config)?;
+    let packages = rendered_packages(&files, &config.get_name());
+    let issues = compat::check(compat::engine(config), &packages);
+    let error = issues
+        .iter()
+        .find(|issue| issue.severity == Severity::Error);
+    if let (Some(issue), Some(driver)) = (error, compat::suggested_driver(&packages)) {
+        let prompt = format!("{} Switch to {}? (Y/n): ", issue, driver);
+        if cumaea::prompt_tf_default(&prompt, None, true) {
+            config.set_driver(driver);
+        }
+    }
+    Ok(())
 }
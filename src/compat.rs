@@ -0,0 +1,283 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::build::{driver_arguments, BuildDirs};
+use crate::config::*;
+use crate::driver::{self, Engine};
+use crate::message::*;
+use crate::TexrsError;
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Packages that need a Unicode engine, XeTeX or LuaTeX.
+const UNICODE_PACKAGES: [&str; 3] = ["fontspec", "unicode-math", "polyglossia"];
+
+/// Packages that only work under LuaTeX.
+const LUA_PACKAGES: [&str; 4] = ["luacode", "luatexja", "luaotfload", "luatextra"];
+
+/// Packages a Unicode engine doesn't need, & that only get in its way.
+const LEGACY_PACKAGES: [&str; 2] = ["inputenc", "ucs"];
+
+/// How bad an incompatibility is: an error can never compile, a warning
+/// compiles with noise or surprises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A package the driver's engine can't, or shouldn't, load.
+#[derive(Debug, Clone)]
+pub struct Incompatibility {
+    pub package: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.package, self.message)
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// The preamble of a LaTeX source: everything before
+/// `\begin{document}`, or the whole source if there's no such line, as
+/// in a `.sty` file.
+/* -------------------------------------------------------------------- */
+pub fn preamble(source: &str) -> &str {
+    match source.find("\\begin{document}") {
+        Some(end) => &source[..end],
+        None => source,
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// The packages loaded with `\usepackage` or `\RequirePackage` in
+/// `source`, comments ignored.
+///
+/// ## Usage
+///
+/// ```rust
+/// let packages = packages(preamble(&source));
+/// assert!(packages.contains(&"fontspec".to_owned()));
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn packages(source: &str) -> Vec<String> {
+    let text: String = source
+        .lines()
+        .map(strip_comment)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut packages = Vec::new();
+    for command in ["\\usepackage", "\\RequirePackage"] {
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(command) {
+            rest = rest[start + command.len()..].trim_start();
+            // Skip the options, if any.
+            if rest.starts_with('[') {
+                match rest.find(']') {
+                    Some(end) => rest = rest[end + 1..].trim_start(),
+                    None => break,
+                }
+            }
+            if let Some(names) = rest.strip_prefix('{') {
+                let Some(end) = names.find('}') else { break };
+                packages.extend(
+                    names[..end]
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_owned),
+                );
+                rest = &names[end..];
+            }
+        }
+    }
+    packages
+}
+
+/// Cuts a line off at its first unescaped `%`.
+pub fn strip_comment(line: &str) -> &str {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '%' if !escaped => return &line[..i],
+            _ => escaped = false,
+        }
+    }
+    line
+}
+
+/// The packages loaded by a set of files: the preamble of `main` & all
+/// of any `.sty` or `.cls` file alongside it.
+pub fn files_packages<'a>(
+    main: &Path,
+    files: impl IntoIterator<Item = (&'a Path, &'a str)>,
+) -> Vec<String> {
+    let mut found = Vec::new();
+    for (path, source) in files {
+        let is_package = path
+            .extension()
+            .is_some_and(|ext| ext == "sty" || ext == "cls");
+        if path == main {
+            found.extend(packages(preamble(source)));
+        } else if is_package {
+            found.extend(packages(source));
+        }
+    }
+    found
+}
+
+/* -------------------------------------------------------------------- */
+/// Checks `packages` against `engine`: Unicode-only packages under
+/// pdfTeX & LuaTeX-only ones under anything else are errors, while
+/// `inputenc` & `ucs` under a Unicode engine are warnings.
+/* -------------------------------------------------------------------- */
+pub fn check(engine: Engine, packages: &[String]) -> Vec<Incompatibility> {
+    let mut issues = Vec::new();
+    for package in packages {
+        let (severity, message) = if LUA_PACKAGES.contains(&package.as_str()) {
+            if engine == Engine::LuaTex {
+                continue;
+            }
+            (Severity::Error, "needs LuaTeX.")
+        } else if UNICODE_PACKAGES.contains(&package.as_str()) {
+            if engine.is_unicode() {
+                continue;
+            }
+            (Severity::Error, "needs XeTeX or LuaTeX.")
+        } else if LEGACY_PACKAGES.contains(&package.as_str()) {
+            if !engine.is_unicode() {
+                continue;
+            }
+            (
+                Severity::Warning,
+                "isn't needed under XeTeX or LuaTeX, & should be removed.",
+            )
+        } else {
+            continue;
+        };
+        issues.push(Incompatibility {
+            package: package.clone(),
+            severity,
+            message: message.to_owned(),
+        });
+    }
+    issues
+}
+
+/// The driver to suggest for `packages`: `lualatex` if any of them needs
+/// LuaTeX, `xelatex` if any needs a Unicode engine, & otherwise none.
+pub fn suggested_driver(packages: &[String]) -> Option<&'static str> {
+    if packages
+        .iter()
+        .any(|package| LUA_PACKAGES.contains(&package.as_str()))
+    {
+        Some(Engine::LuaTex.driver())
+    } else if packages
+        .iter()
+        .any(|package| UNICODE_PACKAGES.contains(&package.as_str()))
+    {
+        Some(Engine::XeTex.driver())
+    } else {
+        None
+    }
+}
+
+/// The engine the project's driver compiles with.
+pub fn engine(config: &ProjectConfig) -> Engine {
+    let driver = driver::from_config(config);
+    driver.engine(&driver_arguments(config, driver.as_ref(), 1))
+}
+
+/// The packages a project loads: the preamble of `tex/JOBNAME.tex` &
+/// all of any `.sty` or `.cls` file beside it.
+pub fn project_packages(dirs: &BuildDirs, jobname: &str) -> Vec<String> {
+    let main = dirs.tex.join(format!("{}.tex", jobname));
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dirs.tex)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    let sources: Vec<(PathBuf, String)> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let source = fs::read_to_string(&path).ok()?;
+            Some((path, source))
+        })
+        .collect();
+    files_packages(
+        &main,
+        sources
+            .iter()
+            .map(|(path, source)| (path.as_path(), source.as_str())),
+    )
+}
+
+/* -------------------------------------------------------------------- */
+/// Checks `packages` against the driver `config` names. Warnings are
+/// reported as events; the first error is returned as a
+/// `TexrsError::IncompatibleDriver`, unless `force` is set, in which case
+/// it's reported like a warning.
+///
+/// ## Usage
+///
+/// ```rust
+/// let packages = project_packages(&dirs, &config.get_name());
+/// check_driver(&config, &packages, false, MessageFormat::Human)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn check_driver(
+    config: &ProjectConfig,
+    packages: &[String],
+    force: bool,
+    format: MessageFormat,
+) -> Result<(), TexrsError> {
+    let suggested = suggested_driver(packages).map(str::to_owned);
+    for issue in check(engine(config), packages) {
+        if issue.severity == Severity::Error && !force {
+            return Err(TexrsError::IncompatibleDriver {
+                driver: config.get_driver(),
+                issue: issue.to_string(),
+                suggested,
+            });
+        }
+        emit(
+            format,
+            Event::Incompatibility {
+                driver: config.get_driver(),
+                package: issue.package,
+                message: issue.message,
+                suggested_driver: match issue.severity {
+                    Severity::Error => suggested.clone(),
+                    Severity::Warning => None,
+                },
+            },
+        );
+    }
+    Ok(())
+}
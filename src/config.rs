@@ -16,34 +16,539 @@
 // | <https://github.com/ethanbarry> is the author's profile.                                                                          |
 // + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
 
-use crate::DocumentType;
+use crate::presentation::Variant;
+use crate::{DocumentType, TexrsError};
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
+use std::collections::BTreeMap;
+use std::env;
 use std::error::Error;
-use std::fs::File;
-use std::io::Write;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use toml;
 
-/// The ProjectConfig struct stores six pieces of
-/// information about the project: what name, driver,
+/// The name of the project manifest, found at the project root.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// The default upper bound on driver passes per build.
+pub const DEFAULT_MAX_PASSES: u32 = 5;
+
+/// The ProjectConfig struct stores sixteen pieces of
+/// information about the project: what name, driver, driver executable,
 /// & whether citations or graphics are used, whatever arguments are passed,
-/// & what type of document it is.
+/// what type of document it is, which user template it was made from,
+/// how many driver passes a build may take,
+/// the arguments for each tool the build runs, the details that fill in
+/// the document's template, which variants of a presentation to build,
+/// the details that fill in a thesis's front matter, how a math
+/// article sets up its theorems, where the project keeps its sources, &
+/// how it was made a git repository.
 ///
 /// ## Structure
 /// name: String,
 /// driver: String,
+/// driver_path: Option<String>,
 /// arguments: Option<Vec<String>>
 /// citations: bool,
 /// graphics: bool,
-/// doctype: DocumentType
+/// doctype: DocumentType,
+/// template: Option<String>,
+/// max_passes: Option<u32>,
+/// tools: Option<ToolArguments>,
+/// document: Option<DocumentInfo>,
+/// presentation: Option<PresentationInfo>,
+/// thesis: Option<ThesisInfo>,
+/// math: Option<MathInfo>,
+/// layout: Option<Layout>,
+/// vcs: Option<VcsInfo>
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
     name: String,
     driver: String,
+    driver_path: Option<String>,
     arguments: Option<Vec<String>>,
     citations: bool,
     graphics: bool,
     doctype: DocumentType,
+    template: Option<String>,
+    max_passes: Option<u32>,
+    tools: Option<ToolArguments>,
+    document: Option<DocumentInfo>,
+    presentation: Option<PresentationInfo>,
+    thesis: Option<ThesisInfo>,
+    math: Option<MathInfo>,
+    layout: Option<Layout>,
+    vcs: Option<VcsInfo>,
+}
+
+/// The `[document]` table: details about the document itself, used to
+/// fill in the placeholders of its template.
+///
+/// ## Structure
+/// title: Option<String>,
+/// author: Option<String>,
+/// email: Option<String>,
+/// institution: Option<String>,
+/// course: Option<String>,
+/// date: Option<String>,
+/// paper: Option<String>,
+/// language: Option<String>,
+/// font: Option<String>
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DocumentInfo {
+    title: Option<String>,
+    author: Option<String>,
+    email: Option<String>,
+    institution: Option<String>,
+    course: Option<String>,
+    date: Option<String>,
+    paper: Option<String>,
+    language: Option<String>,
+    font: Option<String>,
+}
+
+impl DocumentInfo {
+    /// `None` means the project name stands in for the title.
+    pub fn get_title(&self) -> Option<String> {
+        self.title.clone()
+    }
+
+    pub fn get_author(&self) -> String {
+        self.author.clone().unwrap_or_default()
+    }
+
+    pub fn get_email(&self) -> String {
+        self.email.clone().unwrap_or_default()
+    }
+
+    /// The school or company on an article's title page.
+    pub fn get_institution(&self) -> String {
+        self.institution.clone().unwrap_or_default()
+    }
+
+    /// The course or series an article was written for.
+    pub fn get_course(&self) -> String {
+        self.course.clone().unwrap_or_default()
+    }
+
+    /// Defaults to `\today`, so the date is filled in at build time.
+    pub fn get_date(&self) -> String {
+        self.date.clone().unwrap_or_else(|| "\\today".to_owned())
+    }
+
+    pub fn get_paper(&self) -> String {
+        self.paper
+            .clone()
+            .unwrap_or_else(|| "letterpaper".to_owned())
+    }
+
+    /// A babel language name; defaults to `english`.
+    pub fn get_language(&self) -> String {
+        self.language
+            .clone()
+            .unwrap_or_else(|| "english".to_owned())
+    }
+
+    pub fn get_font(&self) -> String {
+        self.font.clone().unwrap_or_else(|| "ebgaramond".to_owned())
+    }
+
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+    }
+
+    pub fn set_author(&mut self, author: Option<String>) {
+        self.author = author;
+    }
+
+    pub fn set_email(&mut self, email: Option<String>) {
+        self.email = email;
+    }
+
+    pub fn set_institution(&mut self, institution: Option<String>) {
+        self.institution = institution;
+    }
+
+    pub fn set_course(&mut self, course: Option<String>) {
+        self.course = course;
+    }
+
+    pub fn set_date(&mut self, date: Option<String>) {
+        self.date = date;
+    }
+
+    pub fn set_paper(&mut self, paper: Option<String>) {
+        self.paper = paper;
+    }
+
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.language = language;
+    }
+
+    pub fn set_font(&mut self, font: Option<String>) {
+        self.font = font;
+    }
+}
+
+/// The `[presentation]` table: the variants a build makes of a Beamer
+/// presentation, each its own PDF in `target/`.
+///
+/// ## Structure
+/// variants: Option<Vec<Variant>>
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct PresentationInfo {
+    variants: Option<Vec<Variant>>,
+}
+
+impl PresentationInfo {
+    /// Defaults to just the slides.
+    pub fn get_variants(&self) -> Vec<Variant> {
+        self.variants
+            .clone()
+            .unwrap_or_else(|| vec![Variant::Slides])
+    }
+
+    pub fn set_variants(&mut self, variants: Option<Vec<Variant>>) {
+        self.variants = variants;
+    }
+}
+
+/// The `[thesis]` table: the details texrs writes into a thesis's
+/// front matter on every build, & the university profile that sets its
+/// layout & wording.
+///
+/// ## Structure
+/// university: Option<String>,
+/// department: Option<String>,
+/// degree: Option<String>,
+/// advisor: Option<String>,
+/// submission_date: Option<String>,
+/// profile: Option<String>
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ThesisInfo {
+    university: Option<String>,
+    department: Option<String>,
+    degree: Option<String>,
+    advisor: Option<String>,
+    submission_date: Option<String>,
+    profile: Option<String>,
+}
+
+impl ThesisInfo {
+    pub fn get_university(&self) -> String {
+        self.university.clone().unwrap_or_default()
+    }
+
+    pub fn get_department(&self) -> String {
+        self.department.clone().unwrap_or_default()
+    }
+
+    pub fn get_degree(&self) -> String {
+        self.degree.clone().unwrap_or_default()
+    }
+
+    pub fn get_advisor(&self) -> String {
+        self.advisor.clone().unwrap_or_default()
+    }
+
+    /// Defaults to `\today`, like the document's date.
+    pub fn get_submission_date(&self) -> String {
+        self.submission_date
+            .clone()
+            .unwrap_or_else(|| "\\today".to_owned())
+    }
+
+    /// Defaults to the `generic` profile.
+    pub fn get_profile(&self) -> String {
+        self.profile.clone().unwrap_or_else(|| "generic".to_owned())
+    }
+
+    pub fn set_university(&mut self, university: Option<String>) {
+        self.university = university;
+    }
+
+    pub fn set_department(&mut self, department: Option<String>) {
+        self.department = department;
+    }
+
+    pub fn set_degree(&mut self, degree: Option<String>) {
+        self.degree = degree;
+    }
+
+    pub fn set_advisor(&mut self, advisor: Option<String>) {
+        self.advisor = advisor;
+    }
+
+    pub fn set_submission_date(&mut self, submission_date: Option<String>) {
+        self.submission_date = submission_date;
+    }
+
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+    }
+}
+
+/// How theorems are numbered: within each section, or straight through
+/// the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Numbering {
+    Section,
+    Global,
+}
+
+/// The `[math]` table: how a math article sets up its theorem
+/// environments. With `shared_counter`, theorems, lemmas & the rest count
+/// together (Theorem 1.1, Lemma 1.2); otherwise each counts on its own.
+/// `unicode_math` loads `unicode-math` when the driver supports it.
+///
+/// ## Structure
+/// numbering: Option<Numbering>,
+/// shared_counter: Option<bool>,
+/// unicode_math: Option<bool>
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MathInfo {
+    numbering: Option<Numbering>,
+    shared_counter: Option<bool>,
+    unicode_math: Option<bool>,
+}
+
+impl MathInfo {
+    /// Defaults to numbering within sections.
+    pub fn get_numbering(&self) -> Numbering {
+        self.numbering.unwrap_or(Numbering::Section)
+    }
+
+    /// Defaults to `true`.
+    pub fn get_shared_counter(&self) -> bool {
+        self.shared_counter.unwrap_or(true)
+    }
+
+    /// Defaults to `true`.
+    pub fn get_unicode_math(&self) -> bool {
+        self.unicode_math.unwrap_or(true)
+    }
+
+    pub fn set_numbering(&mut self, numbering: Option<Numbering>) {
+        self.numbering = numbering;
+    }
+
+    pub fn set_shared_counter(&mut self, shared_counter: Option<bool>) {
+        self.shared_counter = shared_counter;
+    }
+
+    pub fn set_unicode_math(&mut self, unicode_math: Option<bool>) {
+        self.unicode_math = unicode_math;
+    }
+}
+
+/// The `[layout]` table: where a project keeps its sources, relative to
+/// its root. Projects made by `texrs new` use the defaults; `texrs init`
+/// writes the table for a directory it adopts as it is.
+///
+/// ## Structure
+/// tex: Option<String>,
+/// bib: Option<String>,
+/// graphics: Option<String>
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Layout {
+    tex: Option<String>,
+    bib: Option<String>,
+    graphics: Option<String>,
+}
+
+impl Layout {
+    /// Defaults to `tex`.
+    pub fn get_tex(&self) -> String {
+        self.tex.clone().unwrap_or_else(|| "tex".to_owned())
+    }
+
+    /// Defaults to `bib`.
+    pub fn get_bib(&self) -> String {
+        self.bib.clone().unwrap_or_else(|| "bib".to_owned())
+    }
+
+    /// Defaults to `graphics`.
+    pub fn get_graphics(&self) -> String {
+        self.graphics
+            .clone()
+            .unwrap_or_else(|| "graphics".to_owned())
+    }
+
+    pub fn set_tex(&mut self, tex: Option<String>) {
+        self.tex = tex;
+    }
+
+    pub fn set_bib(&mut self, bib: Option<String>) {
+        self.bib = bib;
+    }
+
+    pub fn set_graphics(&mut self, graphics: Option<String>) {
+        self.graphics = graphics;
+    }
+}
+
+/// How a new project's `.gitattributes` treats PDFs & images: as binary
+/// files, as Git LFS files, or not at all, in which case there's no
+/// `.gitattributes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Attributes {
+    Binary,
+    Lfs,
+    None,
+}
+
+/// The `[vcs]` table: whether a new project is made a git repository, &
+/// how. `message` may use `<<name>>`, `<<title>>` & `<<author>>`;
+/// `user_name` & `user_email` are set in the repository's own config.
+///
+/// ## Structure
+/// enabled: Option<bool>,
+/// message: Option<String>,
+/// branch: Option<String>,
+/// gitignore: Option<bool>,
+/// attributes: Option<Attributes>,
+/// user_name: Option<String>,
+/// user_email: Option<String>
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VcsInfo {
+    enabled: Option<bool>,
+    message: Option<String>,
+    branch: Option<String>,
+    gitignore: Option<bool>,
+    attributes: Option<Attributes>,
+    user_name: Option<String>,
+    user_email: Option<String>,
+}
+
+impl VcsInfo {
+    /// Defaults to `true`.
+    pub fn get_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Defaults to `Initialize repository.`
+    pub fn get_message(&self) -> String {
+        self.message
+            .clone()
+            .unwrap_or_else(|| "Initialize repository.".to_owned())
+    }
+
+    /// `None` leaves the branch name to git.
+    pub fn get_branch(&self) -> Option<String> {
+        self.branch.clone()
+    }
+
+    /// Defaults to `true`.
+    pub fn get_gitignore(&self) -> bool {
+        self.gitignore.unwrap_or(true)
+    }
+
+    /// Defaults to marking PDFs & images binary.
+    pub fn get_attributes(&self) -> Attributes {
+        self.attributes.unwrap_or(Attributes::Binary)
+    }
+
+    pub fn get_user_name(&self) -> Option<String> {
+        self.user_name.clone()
+    }
+
+    pub fn get_user_email(&self) -> Option<String> {
+        self.user_email.clone()
+    }
+
+    pub fn set_enabled(&mut self, enabled: Option<bool>) {
+        self.enabled = enabled;
+    }
+
+    pub fn set_message(&mut self, message: Option<String>) {
+        self.message = message;
+    }
+
+    pub fn set_branch(&mut self, branch: Option<String>) {
+        self.branch = branch;
+    }
+
+    pub fn set_gitignore(&mut self, gitignore: Option<bool>) {
+        self.gitignore = gitignore;
+    }
+
+    pub fn set_attributes(&mut self, attributes: Option<Attributes>) {
+        self.attributes = attributes;
+    }
+
+    pub fn set_user_name(&mut self, user_name: Option<String>) {
+        self.user_name = user_name;
+    }
+
+    pub fn set_user_email(&mut self, user_email: Option<String>) {
+        self.user_email = user_email;
+    }
+}
+
+/// The `[tools]` table: argument lists for each tool a build runs.
+/// `passes` maps a pass number to driver arguments that replace the
+/// usual ones on that pass. Unless `defaults` is `false`, the driver
+/// also gets its default arguments (for the LaTeX engines
+/// `-interaction=nonstopmode`, `-halt-on-error` & `-file-line-error`),
+/// each unless the arguments already set that flag.
+///
+/// ## Structure
+/// driver: Option<Vec<String>>,
+/// biber: Option<Vec<String>>,
+/// makeindex: Option<Vec<String>>,
+/// passes: Option<BTreeMap<String, Vec<String>>>,
+/// defaults: Option<bool>
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ToolArguments {
+    driver: Option<Vec<String>>,
+    biber: Option<Vec<String>>,
+    makeindex: Option<Vec<String>>,
+    passes: Option<BTreeMap<String, Vec<String>>>,
+    defaults: Option<bool>,
+}
+
+impl ToolArguments {
+    pub fn get_driver(&self) -> Option<Vec<String>> {
+        self.driver.to_owned()
+    }
+
+    pub fn get_biber(&self) -> Vec<String> {
+        self.biber.to_owned().unwrap_or_default()
+    }
+
+    pub fn get_makeindex(&self) -> Vec<String> {
+        self.makeindex.to_owned().unwrap_or_default()
+    }
+
+    /// The driver arguments overriding the usual ones on pass `pass`.
+    pub fn get_pass(&self, pass: u32) -> Option<Vec<String>> {
+        self.passes.as_ref()?.get(&pass.to_string()).cloned()
+    }
+
+    pub fn get_defaults(&self) -> bool {
+        self.defaults.unwrap_or(true)
+    }
+
+    pub fn set_driver(&mut self, driver: Option<Vec<String>>) {
+        self.driver = driver;
+    }
+
+    pub fn set_biber(&mut self, biber: Option<Vec<String>>) {
+        self.biber = biber;
+    }
+
+    pub fn set_makeindex(&mut self, makeindex: Option<Vec<String>>) {
+        self.makeindex = makeindex;
+    }
+
+    pub fn set_defaults(&mut self, defaults: Option<bool>) {
+        self.defaults = defaults;
+    }
 }
 
 impl ProjectConfig {
@@ -55,6 +560,11 @@ impl ProjectConfig {
         self.driver.clone()
     }
 
+    /// A custom executable for the driver, if the config sets one.
+    pub fn get_driver_path(&self) -> Option<String> {
+        self.driver_path.clone()
+    }
+
     pub fn get_arguments(&self) -> Option<Vec<String>> {
         self.arguments.to_owned()
     }
@@ -71,6 +581,45 @@ impl ProjectConfig {
         self.doctype
     }
 
+    /// The user template the project was made from; `None` for a
+    /// built-in one.
+    pub fn get_template(&self) -> Option<String> {
+        self.template.clone()
+    }
+
+    pub fn get_document(&self) -> DocumentInfo {
+        self.document.clone().unwrap_or_default()
+    }
+
+    pub fn get_presentation(&self) -> PresentationInfo {
+        self.presentation.clone().unwrap_or_default()
+    }
+
+    pub fn get_thesis(&self) -> ThesisInfo {
+        self.thesis.clone().unwrap_or_default()
+    }
+
+    pub fn get_math(&self) -> MathInfo {
+        self.math.clone().unwrap_or_default()
+    }
+
+    pub fn get_vcs(&self) -> VcsInfo {
+        self.vcs.clone().unwrap_or_default()
+    }
+
+    pub fn get_layout(&self) -> Layout {
+        self.layout.clone().unwrap_or_default()
+    }
+
+    pub fn get_tools(&self) -> ToolArguments {
+        self.tools.clone().unwrap_or_default()
+    }
+
+    /// Falls back to `DEFAULT_MAX_PASSES` when unset; never returns zero.
+    pub fn get_max_passes(&self) -> u32 {
+        self.max_passes.unwrap_or(DEFAULT_MAX_PASSES).max(1)
+    }
+
     pub fn set_name(&mut self, name: &str) {
         self.name = name.to_owned();
     }
@@ -79,6 +628,10 @@ impl ProjectConfig {
         self.driver = driver.to_owned();
     }
 
+    pub fn set_driver_path(&mut self, driver_path: Option<String>) {
+        self.driver_path = driver_path;
+    }
+
     pub fn set_arguments(&mut self, arguments: Option<Vec<String>>) {
         self.arguments = arguments;
     }
@@ -95,14 +648,60 @@ impl ProjectConfig {
         self.doctype = doctype;
     }
 
+    pub fn set_template(&mut self, template: Option<String>) {
+        self.template = template;
+    }
+
+    pub fn set_document(&mut self, document: Option<DocumentInfo>) {
+        self.document = document;
+    }
+
+    pub fn set_presentation(&mut self, presentation: Option<PresentationInfo>) {
+        self.presentation = presentation;
+    }
+
+    pub fn set_thesis(&mut self, thesis: Option<ThesisInfo>) {
+        self.thesis = thesis;
+    }
+
+    pub fn set_math(&mut self, math: Option<MathInfo>) {
+        self.math = math;
+    }
+
+    pub fn set_vcs(&mut self, vcs: Option<VcsInfo>) {
+        self.vcs = vcs;
+    }
+
+    pub fn set_layout(&mut self, layout: Option<Layout>) {
+        self.layout = layout;
+    }
+
+    pub fn set_tools(&mut self, tools: Option<ToolArguments>) {
+        self.tools = tools;
+    }
+
+    pub fn set_max_passes(&mut self, max_passes: Option<u32>) {
+        self.max_passes = max_passes;
+    }
+
     pub fn new() -> ProjectConfig {
         ProjectConfig {
             name: "document1".to_owned(),
             driver: "pdflatex".to_owned(),
+            driver_path: None,
             arguments: None,
             citations: true,
             graphics: true,
             doctype: DocumentType::Letter,
+            template: None,
+            max_passes: None,
+            tools: None,
+            document: user_config().document(),
+            presentation: None,
+            thesis: None,
+            math: None,
+            layout: None,
+            vcs: user_config().vcs.clone(),
         }
     }
 }
@@ -113,10 +712,336 @@ impl Default for ProjectConfig {
     }
 }
 
-pub fn write_project_config(config: &ProjectConfig) -> Result<(), Box<dyn Error>> {
-    let root_dir = config.get_name() + "/";
+/// The directory texrs keeps its per-user files in: `texrs` under
+/// `$XDG_CONFIG_HOME`, or under `~/.config` if that isn't set.
+pub fn user_config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .map(|dir| dir.join("texrs"))
+}
+
+/// The user config, `config.toml` in `user_config_dir()`: the details
+/// every new project starts with, its `[vcs]` table included, &
+/// defaults for each doctype that replace those of its built-in
+/// template.
+///
+/// ## Structure
+/// author: Option<String>,
+/// email: Option<String>,
+/// institution: Option<String>,
+/// paper: Option<String>,
+/// language: Option<String>,
+/// font: Option<String>,
+/// doctypes: Option<BTreeMap<DocumentType, DoctypeDefaults>>,
+/// vcs: Option<VcsInfo>
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserConfig {
+    author: Option<String>,
+    email: Option<String>,
+    institution: Option<String>,
+    paper: Option<String>,
+    language: Option<String>,
+    font: Option<String>,
+    doctypes: Option<BTreeMap<DocumentType, DoctypeDefaults>>,
+    vcs: Option<VcsInfo>,
+}
+
+/// A `[doctypes.NAME]` table of the user config. Whatever it leaves out
+/// comes from the built-in template.
+///
+/// ## Structure
+/// driver: Option<String>,
+/// citations: Option<bool>,
+/// graphics: Option<bool>
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DoctypeDefaults {
+    driver: Option<String>,
+    citations: Option<bool>,
+    graphics: Option<bool>,
+}
+
+impl DoctypeDefaults {
+    pub fn get_driver(&self) -> Option<String> {
+        self.driver.clone()
+    }
+
+    pub fn get_citations(&self) -> Option<bool> {
+        self.citations
+    }
+
+    pub fn get_graphics(&self) -> Option<bool> {
+        self.graphics
+    }
+}
+
+impl UserConfig {
+    pub fn get_author(&self) -> Option<String> {
+        self.author.clone()
+    }
+
+    pub fn get_email(&self) -> Option<String> {
+        self.email.clone()
+    }
+
+    pub fn get_institution(&self) -> Option<String> {
+        self.institution.clone()
+    }
+
+    pub fn get_paper(&self) -> Option<String> {
+        self.paper.clone()
+    }
+
+    pub fn get_language(&self) -> Option<String> {
+        self.language.clone()
+    }
+
+    pub fn get_font(&self) -> Option<String> {
+        self.font.clone()
+    }
+
+    /// The defaults for `doctype`; empty if the user config has none.
+    pub fn get_doctype(&self, doctype: DocumentType) -> DoctypeDefaults {
+        self.doctypes
+            .as_ref()
+            .and_then(|doctypes| doctypes.get(&doctype))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The `[document]` table a new project starts with, or `None` if
+    /// the user config sets none of it.
+    pub fn document(&self) -> Option<DocumentInfo> {
+        let document = DocumentInfo {
+            author: self.get_author(),
+            email: self.get_email(),
+            institution: self.get_institution(),
+            paper: self.get_paper(),
+            language: self.get_language(),
+            font: self.get_font(),
+            ..Default::default()
+        };
+        let fields = [
+            &document.author,
+            &document.email,
+            &document.institution,
+            &document.paper,
+            &document.language,
+            &document.font,
+        ];
+        fields
+            .iter()
+            .any(|field| field.is_some())
+            .then_some(document)
+    }
+}
+
+static USER_CONFIG: OnceLock<UserConfig> = OnceLock::new();
+
+/* -------------------------------------------------------------------- */
+/// Reads the user config, if there is one, & caches it for the rest of
+/// the run. Call this before anything uses `user_config()`, so that a
+/// mistake in the file is reported instead of ignored.
+///
+/// ## Usage
+///
+/// ```rust
+/// config::load_user_config()?;
+/// let config = ProjectConfig::new(); // Has the user's author, &c.
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn load_user_config() -> Result<&'static UserConfig, Box<dyn Error>> {
+    if let Some(config) = USER_CONFIG.get() {
+        return Ok(config);
+    }
+    let config = read_user_config()?;
+    Ok(USER_CONFIG.get_or_init(|| config))
+}
+
+/// The cached user config. If it hasn't been loaded yet, it is now, &
+/// one that doesn't parse counts as empty.
+pub fn user_config() -> &'static UserConfig {
+    USER_CONFIG.get_or_init(|| read_user_config().unwrap_or_default())
+}
+
+fn read_user_config() -> Result<UserConfig, Box<dyn Error>> {
+    let Some(path) = user_config_dir().map(|dir| dir.join(CONFIG_FILE)) else {
+        return Ok(UserConfig::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => parse_toml(&path, &contents),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(UserConfig::default()),
+        Err(err) => Err(Box::new(TexrsError::IoError(err))),
+    }
+}
+
+/// Writes `config` to the `config.toml` of the project at `root`.
+pub fn write_project_config_at(root: &Path, config: &ProjectConfig) -> Result<(), Box<dyn Error>> {
     let toml_str = toml::to_string(&config)?;
-    let mut file = File::create(root_dir + "config.toml")?;
+    let mut file = File::create(root.join(CONFIG_FILE))?;
     file.write_all(toml_str.as_bytes())?;
     Ok(())
 }
+
+/* -------------------------------------------------------------------- */
+/// Reads & parses the project config at `path`. A missing file is a
+/// `TexrsError::MissingConfig`; invalid TOML is a `TexrsError::ConfigParse`
+/// pointing at the offending line & column.
+/* -------------------------------------------------------------------- */
+pub fn read_project_config(path: &Path) -> Result<ProjectConfig, Box<dyn Error>> {
+    let file_string = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(Box::new(TexrsError::MissingConfig(path.to_path_buf())));
+        }
+        Err(err) => return Err(Box::new(TexrsError::IoError(err))),
+    };
+
+    parse_toml(path, &file_string)
+}
+
+/// Parses the TOML in `contents`, read from `path`, into a `T`; an error
+/// is a `TexrsError::ConfigParse` pointing at the offending spot.
+fn parse_toml<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    contents: &str,
+) -> Result<T, Box<dyn Error>> {
+    toml::from_str(contents).map_err(|err| {
+        let offset = err.span().map(|span| span.start).unwrap_or(0);
+        let before = &contents[..offset.min(contents.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+        Box::new(TexrsError::ConfigParse {
+            path: path.to_path_buf(),
+            line,
+            column,
+            snippet: contents.lines().nth(line - 1).unwrap_or("").to_owned(),
+            message: err.message().to_owned(),
+        }) as Box<dyn Error>
+    })
+}
+
+/* -------------------------------------------------------------------- */
+/// Finds the project that `start` belongs to by walking up from it until
+/// a directory holding a `config.toml` turns up. `start` may be the
+/// manifest itself, or any file or directory inside the project. Returns
+/// the project root & its config; every project path is relative to
+/// that root, not to the current directory.
+///
+/// ## Usage
+///
+/// ```rust
+/// let (root, config) = find_project(&std::env::current_dir()?)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn find_project(start: &Path) -> Result<(PathBuf, ProjectConfig), Box<dyn Error>> {
+    let start = start
+        .canonicalize()
+        .map_err(|_| TexrsError::MissingConfig(start.to_path_buf()))?;
+    let start = if start.is_file() {
+        start.parent().unwrap_or(&start).to_path_buf()
+    } else {
+        start
+    };
+
+    for dir in start.ancestors() {
+        let manifest = dir.join(CONFIG_FILE);
+        if manifest.is_file() {
+            let config = read_project_config(&manifest)?;
+            return Ok((dir.to_path_buf(), config));
+        }
+    }
+
+    Err(Box::new(TexrsError::MissingConfig(start)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `contents` as a project config, expecting a parse error.
+    fn parse_error(contents: &str) -> (usize, usize, String, String) {
+        let err = parse_toml::<ProjectConfig>(Path::new("config.toml"), contents)
+            .err()
+            .expect("the config parsed");
+        match *err.downcast::<TexrsError>().expect("not a TexrsError") {
+            TexrsError::ConfigParse {
+                line,
+                column,
+                snippet,
+                message,
+                ..
+            } => (line, column, snippet, message),
+            err => panic!("not a ConfigParse error: {}", err),
+        }
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let (line, column, snippet, message) = parse_error("name = \"paper\"\ndriver = pdflatex\n");
+        assert_eq!((line, column), (2, 10));
+        assert_eq!(snippet, "driver = pdflatex");
+        assert!(message.starts_with("invalid string"), "{}", message);
+
+        // An unclosed header points past its end.
+        let (line, column, snippet, _) = parse_error("name = \"paper\"\n[document\n");
+        assert_eq!((line, column, snippet.as_str()), (2, 10, "[document"));
+    }
+
+    #[test]
+    fn type_errors() {
+        let (line, column, snippet, message) =
+            parse_error("name = \"paper\"\ndriver = \"pdflatex\"\ncitations = \"yes\"\n");
+        assert_eq!((line, column), (3, 13));
+        assert_eq!(snippet, "citations = \"yes\"");
+        assert_eq!(message, "invalid type: string \"yes\", expected a boolean");
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let (line, column, snippet, message) = parse_error("name = \"paper\"\n[vcs]\nbogus = 1\n");
+        assert_eq!((line, column, snippet.as_str()), (3, 1, "bogus = 1"));
+        assert!(message.starts_with("unknown field `bogus`"), "{}", message);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        // `Ü` is two bytes, but the caret lines up with characters.
+        let (line, column, _, _) = parse_error("title = \"Über\" x\n");
+        assert_eq!((line, column), (1, 16));
+        // A `\r` belongs to the line before.
+        let (line, column, snippet, _) = parse_error("name = \"paper\"\r\ndriver = pdflatex\r\n");
+        assert_eq!(
+            (line, column, snippet.as_str()),
+            (2, 10, "driver = pdflatex")
+        );
+    }
+
+    #[test]
+    fn errors_without_a_span() {
+        let (line, column, snippet, message) = parse_error("name = \"paper\"");
+        assert_eq!((line, column, snippet.as_str()), (1, 1, "name = \"paper\""));
+        assert_eq!(message, "missing field `driver`");
+    }
+
+    #[test]
+    fn display_points_at_the_column() {
+        let err = parse_toml::<ProjectConfig>(
+            Path::new("config.toml"),
+            "name = \"paper\"\ndriver = pdflatex\n",
+        )
+        .err()
+        .unwrap();
+        let shown = err.to_string();
+        assert!(shown.starts_with("config.toml:2:10: "), "{}", shown);
+        assert!(
+            shown.ends_with("  |\n2 | driver = pdflatex\n  |          ^"),
+            "{}",
+            shown
+        );
+    }
+}
@@ -0,0 +1,279 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::build::BuildDirs;
+use crate::config::*;
+
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+/// Arguments the classic LaTeX engines get unless the config sets the
+/// same flag, so that an error ends the build instead of waiting for
+/// terminal input.
+const LATEX_DEFAULT_ARGUMENTS: [&str; 3] = [
+    "-interaction=nonstopmode",
+    "-halt-on-error",
+    "-file-line-error",
+];
+
+/// latexmk passes the engine flags through, & needs telling to make a PDF.
+const LATEXMK_DEFAULT_ARGUMENTS: [&str; 4] = [
+    "-pdf",
+    "-interaction=nonstopmode",
+    "-halt-on-error",
+    "-file-line-error",
+];
+
+/// The TeX engine that ends up compiling the document, which decides
+/// what packages the preamble can load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    PdfTex,
+    XeTex,
+    LuaTex,
+}
+
+impl Engine {
+    /// The usual driver for the engine.
+    pub fn driver(&self) -> &'static str {
+        match self {
+            Engine::PdfTex => "pdflatex",
+            Engine::XeTex => "xelatex",
+            Engine::LuaTex => "lualatex",
+        }
+    }
+
+    /// Whether the engine reads Unicode input & OpenType fonts natively.
+    pub fn is_unicode(&self) -> bool {
+        !matches!(self, Engine::PdfTex)
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// A TeX engine or build tool that turns the main `.tex` file into a PDF.
+/// Each implementation knows its own command line; `command` puts the
+/// pieces together & runs the driver from `tex/` with the project's
+/// search paths exported.
+/* -------------------------------------------------------------------- */
+pub trait Driver {
+    /// The name used in config files & messages, e.g. `pdflatex`.
+    fn name(&self) -> &str;
+
+    /// The executable to run.
+    fn program(&self) -> &str;
+
+    /// Arguments added unless the config already sets the same flag.
+    fn default_arguments(&self) -> &[&str];
+
+    /// Arguments that send every output & auxiliary file to `target`.
+    fn output_arguments(&self, target: &Path) -> Vec<String>;
+
+    /// Arguments that add `dirs` to the input search path, for drivers
+    /// that don't read `TEXINPUTS`.
+    fn search_path_arguments(&self, _dirs: &[&Path]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The engine the driver compiles with, given the config `arguments`.
+    fn engine(&self, arguments: &[String]) -> Engine;
+
+    /// Whether the driver runs biber itself when the document needs it.
+    fn handles_bibliography(&self) -> bool;
+
+    /// Whether the driver reruns itself until the document is stable.
+    fn handles_reruns(&self) -> bool;
+
+    /// Assembles the command that compiles `source`, a file name relative
+    /// to `tex/`, with the already resolved config `arguments`.
+    fn command(
+        &self,
+        dirs: &BuildDirs,
+        source: &str,
+        arguments: &[String],
+    ) -> Result<Command, Box<dyn Error>> {
+        let mut command = Command::new(self.program());
+        command
+            .args(arguments)
+            .args(self.output_arguments(&dirs.target))
+            .args(self.search_path_arguments(&[&dirs.tex, &dirs.graphics]))
+            .arg(source)
+            .current_dir(&dirs.tex)
+            .env("TEXINPUTS", dirs.texinputs()?)
+            .env("BIBINPUTS", dirs.bibinputs()?);
+        Ok(command)
+    }
+}
+
+/// pdflatex, xelatex, lualatex, & any other engine with the usual TeX
+/// command line. texrs drives the passes & runs biber itself.
+pub struct Latex {
+    engine: String,
+    program: String,
+}
+
+impl Driver for Latex {
+    fn name(&self) -> &str {
+        &self.engine
+    }
+
+    fn program(&self) -> &str {
+        &self.program
+    }
+
+    fn default_arguments(&self) -> &[&str] {
+        &LATEX_DEFAULT_ARGUMENTS
+    }
+
+    fn output_arguments(&self, target: &Path) -> Vec<String> {
+        vec![format!("-output-directory={}", target.display())]
+    }
+
+    fn engine(&self, _arguments: &[String]) -> Engine {
+        if self.engine.starts_with("xe") {
+            Engine::XeTex
+        } else if self.engine.starts_with("lua") {
+            Engine::LuaTex
+        } else {
+            Engine::PdfTex
+        }
+    }
+
+    fn handles_bibliography(&self) -> bool {
+        false
+    }
+
+    fn handles_reruns(&self) -> bool {
+        false
+    }
+}
+
+/// Tectonic is self-contained: it reruns itself & runs biber on its own,
+/// never prompts, & ignores `TEXINPUTS`.
+pub struct Tectonic {
+    program: String,
+}
+
+impl Driver for Tectonic {
+    fn name(&self) -> &str {
+        "tectonic"
+    }
+
+    fn program(&self) -> &str {
+        &self.program
+    }
+
+    fn default_arguments(&self) -> &[&str] {
+        &[]
+    }
+
+    fn output_arguments(&self, target: &Path) -> Vec<String> {
+        vec![
+            "--outdir".to_owned(),
+            target.display().to_string(),
+            "--keep-intermediates".to_owned(),
+            "--keep-logs".to_owned(),
+        ]
+    }
+
+    fn search_path_arguments(&self, dirs: &[&Path]) -> Vec<String> {
+        dirs.iter()
+            .flat_map(|dir| ["-Z".to_owned(), format!("search-path={}", dir.display())])
+            .collect()
+    }
+
+    /// Tectonic is built on XeTeX.
+    fn engine(&self, _arguments: &[String]) -> Engine {
+        Engine::XeTex
+    }
+
+    fn handles_bibliography(&self) -> bool {
+        true
+    }
+
+    fn handles_reruns(&self) -> bool {
+        true
+    }
+}
+
+/// latexmk works out the passes, biber & makeindex runs by itself.
+pub struct Latexmk {
+    program: String,
+}
+
+impl Driver for Latexmk {
+    fn name(&self) -> &str {
+        "latexmk"
+    }
+
+    fn program(&self) -> &str {
+        &self.program
+    }
+
+    fn default_arguments(&self) -> &[&str] {
+        &LATEXMK_DEFAULT_ARGUMENTS
+    }
+
+    fn output_arguments(&self, target: &Path) -> Vec<String> {
+        vec![format!("-outdir={}", target.display())]
+    }
+
+    /// latexmk runs pdflatex unless told to use another engine.
+    fn engine(&self, arguments: &[String]) -> Engine {
+        let flag = |names: &[&str]| arguments.iter().any(|arg| names.contains(&arg.as_str()));
+        if flag(&["-xelatex", "-pdfxe"]) {
+            Engine::XeTex
+        } else if flag(&["-lualatex", "-pdflua"]) {
+            Engine::LuaTex
+        } else {
+            Engine::PdfTex
+        }
+    }
+
+    fn handles_bibliography(&self) -> bool {
+        true
+    }
+
+    fn handles_reruns(&self) -> bool {
+        true
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// Picks the driver named by `driver` in the config. `driver_path`, if
+/// set, replaces the executable; unknown names are treated as engines
+/// with the usual TeX command line.
+///
+/// ## Usage
+///
+/// ```rust
+/// let driver = driver::from_config(&config);
+/// println!("Building with {}.", driver.name());
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn from_config(config: &ProjectConfig) -> Box<dyn Driver> {
+    let name = config.get_driver();
+    let program = config.get_driver_path().unwrap_or_else(|| name.clone());
+    match name.as_str() {
+        "tectonic" => Box::new(Tectonic { program }),
+        "latexmk" => Box::new(Latexmk { program }),
+        _ => Box::new(Latex {
+            engine: name,
+            program,
+        }),
+    }
+}
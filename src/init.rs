@@ -0,0 +1,722 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::compat;
+use crate::config::*;
+use crate::message::*;
+use crate::new;
+use crate::{DocumentType, TexrsError};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files with these extensions are sources, & move to `tex/`.
+const SOURCE_EXTENSIONS: [&str; 6] = ["tex", "sty", "cls", "bst", "bbx", "cbx"];
+
+/// Files with these extensions are images, & move to `graphics/`.
+pub const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "pdf", "eps"];
+
+/// The commands whose arguments name images or bibliographies, & are
+/// rewritten when those move.
+const PATH_COMMANDS: [&str; 3] = ["\\includegraphics", "\\addbibresource", "\\bibliography"];
+
+/// Document classes & the doctypes they make. Anything else is an
+/// article; theses & math articles are left out, since their builds
+/// generate files that an adopted document doesn't use.
+const CLASSES: [(&str, DocumentType); 6] = [
+    ("book", DocumentType::Book),
+    ("scrbook", DocumentType::Book),
+    ("memoir", DocumentType::Book),
+    ("beamer", DocumentType::Presentation),
+    ("letter", DocumentType::Letter),
+    ("scrlttr2", DocumentType::Letter),
+];
+
+/// What `init` should do.
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    /// The main file, when the directory holds more than one document.
+    pub main: Option<PathBuf>,
+    /// The driver to use, in place of the one inferred.
+    pub driver: Option<String>,
+    /// Move the files into the usual layout.
+    pub move_files: bool,
+    /// Only report what would be done.
+    pub dry_run: bool,
+    pub format: MessageFormat,
+}
+
+/* -------------------------------------------------------------------- */
+/// What `init` makes of a directory. Every path is relative to its root.
+/* -------------------------------------------------------------------- */
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// The document: the `.tex` file with a `\documentclass`.
+    pub main: PathBuf,
+    pub class: Option<String>,
+    /// Every `.tex`, `.sty` & such, the main file included.
+    pub sources: Vec<PathBuf>,
+    pub bibliographies: Vec<PathBuf>,
+    pub images: Vec<PathBuf>,
+    pub driver: String,
+    pub doctype: DocumentType,
+    pub citations: bool,
+    pub graphics: bool,
+}
+
+impl Analysis {
+    /// The directory the document was compiled from, which its paths
+    /// are relative to.
+    fn main_dir(&self) -> &Path {
+        self.main.parent().unwrap_or(Path::new(""))
+    }
+
+    /// The `[layout]` that fits the directory as it is, or `None` if it
+    /// already has the usual one.
+    fn layout(&self) -> Option<Layout> {
+        let tex = self.main_dir().to_path_buf();
+        let bib = match self.bibliographies.first() {
+            Some(bib) => bib.parent().unwrap_or(Path::new("")).to_path_buf(),
+            None => tex.clone(),
+        };
+        let graphics = match image_dir(&self.images) {
+            Some(dir) => dir,
+            None => tex.clone(),
+        };
+
+        let default = Layout::default();
+        let mut layout = Layout::default();
+        let mut changed = false;
+        let mut set = |dir: &Path, usual: String, setter: fn(&mut Layout, Option<String>)| {
+            let dir = layout_path(dir);
+            if dir != usual {
+                setter(&mut layout, Some(dir));
+                changed = true;
+            }
+        };
+        set(&tex, default.get_tex(), Layout::set_tex);
+        set(&bib, default.get_bib(), Layout::set_bib);
+        set(&graphics, default.get_graphics(), Layout::set_graphics);
+        changed.then_some(layout)
+    }
+
+    /// Where each file goes in the usual layout, leaving out those
+    /// already there.
+    fn moves(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut moves = Vec::new();
+        for source in &self.sources {
+            let relative = source.strip_prefix(self.main_dir()).unwrap_or(source);
+            moves.push((source.clone(), Path::new("tex").join(relative)));
+        }
+        for bib in &self.bibliographies {
+            let name = bib.file_name().unwrap_or_default();
+            moves.push((bib.clone(), Path::new("bib").join(name)));
+        }
+        let common = image_dir(&self.images);
+        for image in &self.images {
+            let relative = match &common {
+                Some(dir) => image.strip_prefix(dir).unwrap_or(image),
+                None => image,
+            };
+            moves.push((image.clone(), Path::new("graphics").join(relative)));
+        }
+        moves.retain(|(from, to)| from != to);
+        moves
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// Adopts the LaTeX directory at `root` as a texrs project: finds its
+/// document, bibliographies & images, infers the driver & whether it
+/// needs citations or graphics, & writes a `config.toml`. With
+/// `move_files` the files move into `tex/`, `bib/` & `graphics/`, & the
+/// paths that reference images & bibliographies are rewritten; without
+/// it, the config gets a `[layout]` table describing the directory as
+/// it is.
+///
+/// ## Usage
+///
+/// ```rust
+/// init_project(Path::new("old-paper"), &InitOptions::default())?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn init_project(root: &Path, options: &InitOptions) -> Result<Analysis, Box<dyn Error>> {
+    let format = options.format;
+    if !root.is_dir() {
+        return Err(Box::new(TexrsError::InitError(format!(
+            "`{}` isn't a directory.",
+            root.display()
+        ))));
+    }
+    if root.join(CONFIG_FILE).exists() {
+        return Err(Box::new(TexrsError::InitError(format!(
+            "`{}` is already a texrs project.",
+            root.display()
+        ))));
+    }
+
+    let mut analysis = analyze(root, options)?;
+    if let Some(driver) = &options.driver {
+        analysis.driver = driver.clone();
+    }
+    report(&analysis, format);
+
+    let mut config = ProjectConfig::new();
+    let name = analysis
+        .main
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    if !new::jobname(&name).is_ok_and(|jobname| jobname == name) {
+        emit(
+            format,
+            Event::Warning {
+                message: format!(
+                    "`{}` isn't a safe jobname; rename it if the build has trouble with it.",
+                    analysis.main.display()
+                ),
+            },
+        );
+    }
+    config.set_name(&name);
+    config.set_driver(&analysis.driver);
+    config.set_citations(analysis.citations);
+    config.set_graphics(analysis.graphics);
+    config.set_doctype(analysis.doctype);
+    // The `[document]` table only fills in templates, which an adopted
+    // document has no use for.
+    config.set_document(None);
+
+    if options.move_files {
+        let moves = analysis.moves();
+        check_moves(root, &moves)?;
+        if options.dry_run {
+            for (from, to) in &moves {
+                emit(
+                    format,
+                    Event::Info {
+                        message: format!("Would move {} to {}.", from.display(), to.display()),
+                    },
+                );
+            }
+            return Ok(analysis);
+        }
+        move_files(root, &moves, format)?;
+        rewrite_sources(root, &analysis, &moves, format)?;
+    } else {
+        config.set_layout(analysis.layout());
+        if options.dry_run {
+            return Ok(analysis);
+        }
+    }
+
+    write_project_config_at(root, &config)?;
+    emit(
+        format,
+        Event::FileCreated {
+            path: root.join(CONFIG_FILE).display().to_string(),
+        },
+    );
+    Ok(analysis)
+}
+
+/// Looks through the directory at `root` for everything `init` needs.
+fn analyze(root: &Path, options: &InitOptions) -> Result<Analysis, Box<dyn Error>> {
+    let mut files = Vec::new();
+    collect(root, Path::new(""), &mut files)?;
+
+    let sources: Vec<PathBuf> = files
+        .iter()
+        .filter(|file| has_extension(file, &SOURCE_EXTENSIONS))
+        .cloned()
+        .collect();
+    let texts: BTreeMap<PathBuf, String> = sources
+        .iter()
+        .filter_map(|source| {
+            let bytes = fs::read(root.join(source)).ok()?;
+            Some((source.clone(), String::from_utf8_lossy(&bytes).into_owned()))
+        })
+        .collect();
+
+    let main = match &options.main {
+        Some(main) => {
+            let main = main.strip_prefix(root).unwrap_or(main).to_path_buf();
+            if !texts.contains_key(&main) {
+                return Err(Box::new(TexrsError::InitError(format!(
+                    "`{}` isn't a .tex file in `{}`.",
+                    main.display(),
+                    root.display()
+                ))));
+            }
+            main
+        }
+        None => find_main(root, &texts, options.format)?,
+    };
+    let main_source = texts.get(&main).cloned().unwrap_or_default();
+
+    // The document's own PDF, from an earlier build, isn't an image.
+    let output = main.with_extension("pdf");
+    let images: Vec<PathBuf> = files
+        .iter()
+        .filter(|file| has_extension(file, &IMAGE_EXTENSIONS) && **file != output)
+        .cloned()
+        .collect();
+    let bibliographies: Vec<PathBuf> = files
+        .iter()
+        .filter(|file| has_extension(file, &["bib"]))
+        .cloned()
+        .collect();
+
+    let packages = compat::files_packages(
+        &main,
+        texts
+            .iter()
+            .map(|(path, text)| (path.as_path(), text.as_str())),
+    );
+    let uses = |needle: &str| {
+        texts.values().any(|text| {
+            text.lines()
+                .any(|line| compat::strip_comment(line).contains(needle))
+        })
+    };
+    let class = document_class(&main_source);
+    let driver = magic_program(&main_source)
+        .or_else(|| compat::suggested_driver(&packages).map(str::to_owned))
+        .unwrap_or_else(|| "pdflatex".to_owned());
+    let doctype = class
+        .as_deref()
+        .and_then(|class| CLASSES.iter().find(|(name, _)| *name == class))
+        .map(|(_, doctype)| *doctype)
+        .unwrap_or(DocumentType::Article);
+
+    Ok(Analysis {
+        citations: !bibliographies.is_empty()
+            || packages.iter().any(|package| package == "biblatex")
+            || uses("\\bibliography{"),
+        graphics: !images.is_empty()
+            || packages
+                .iter()
+                .any(|package| package == "graphicx" || package == "graphics"),
+        main,
+        class,
+        sources,
+        bibliographies,
+        images,
+        driver,
+        doctype,
+    })
+}
+
+/// Picks the document among `texts`: a file with a `\documentclass`,
+/// preferring one with a `\begin{document}`, then the shallowest, then
+/// one called `main`.
+fn find_main(
+    root: &Path,
+    texts: &BTreeMap<PathBuf, String>,
+    format: MessageFormat,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let mut candidates: Vec<&PathBuf> = texts
+        .iter()
+        .filter(|(path, text)| has_extension(path, &["tex"]) && document_class(text).is_some())
+        .map(|(path, _)| path)
+        .collect();
+    candidates.sort_by_key(|path| {
+        let text = &texts[*path];
+        (
+            !text.contains("\\begin{document}"),
+            path.components().count(),
+            path.file_stem().is_none_or(|stem| stem != "main"),
+        )
+    });
+
+    match candidates.as_slice() {
+        [] => Err(Box::new(TexrsError::InitError(format!(
+            "no .tex file in `{}` has a \\documentclass.",
+            root.display()
+        )))),
+        [main] => Ok(main.to_path_buf()),
+        [main, ..] => {
+            emit(
+                format,
+                Event::Warning {
+                    message: format!(
+                        "{} files have a \\documentclass; using {}. Pass --main to pick another.",
+                        candidates.len(),
+                        main.display()
+                    ),
+                },
+            );
+            Ok(main.to_path_buf())
+        }
+    }
+}
+
+/// The class in a source's `\documentclass`, if it has one.
+fn document_class(source: &str) -> Option<String> {
+    let line = source
+        .lines()
+        .map(compat::strip_comment)
+        .find(|line| line.contains("\\documentclass"))?;
+    let mut rest = line[line.find("\\documentclass")? + "\\documentclass".len()..].trim_start();
+    if rest.starts_with('[') {
+        rest = rest[rest.find(']')? + 1..].trim_start();
+    }
+    let class = rest.strip_prefix('{')?;
+    Some(class[..class.find('}')?].trim().to_owned())
+}
+
+/// The engine an editor's magic comment asks for, as in
+/// `% !TEX program = xelatex`.
+fn magic_program(source: &str) -> Option<String> {
+    source
+        .lines()
+        .take_while(|line| line.trim_start().starts_with('%') || line.trim().is_empty())
+        .find_map(|line| {
+            let line = line.trim_start_matches(|c: char| c == '%' || c.is_whitespace());
+            let setting = line
+                .strip_prefix("!TEX")
+                .or_else(|| line.strip_prefix("!TeX"))?;
+            let (key, value) = setting.split_once('=')?;
+            let key = key.trim().to_ascii_lowercase();
+            (key == "program" || key == "ts-program").then(|| value.trim().to_owned())
+        })
+        .filter(|program| !program.is_empty())
+}
+
+/// The single directory every image is in, if there is one below the
+/// root.
+fn image_dir(images: &[PathBuf]) -> Option<PathBuf> {
+    let mut dirs = images.iter().map(|image| {
+        let mut components = image.components();
+        components.next_back();
+        components
+            .next()
+            .map(|first| PathBuf::from(first.as_os_str()))
+    });
+    let first = dirs.next()??;
+    dirs.all(|dir| dir.as_ref() == Some(&first))
+        .then_some(first)
+}
+
+/// A directory as the `[layout]` table spells it, with `/` separators &
+/// `.` for the root.
+fn layout_path(dir: &Path) -> String {
+    let parts: Vec<String> = dir
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if parts.is_empty() {
+        ".".to_owned()
+    } else {
+        parts.join("/")
+    }
+}
+
+fn report(analysis: &Analysis, format: MessageFormat) {
+    let mut lines = vec![format!(
+        "Document: {} ({}).",
+        analysis.main.display(),
+        analysis.class.as_deref().unwrap_or("unknown class")
+    )];
+    lines.push(format!(
+        "{} source(s), {} bibliograph(ies), {} image(s).",
+        analysis.sources.len(),
+        analysis.bibliographies.len(),
+        analysis.images.len()
+    ));
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    lines.push(format!(
+        "Driver: {}; citations: {}; graphics: {}.",
+        analysis.driver,
+        yes_no(analysis.citations),
+        yes_no(analysis.graphics)
+    ));
+    for message in lines {
+        emit(format, Event::Info { message });
+    }
+}
+
+/// Refuses moves that would overwrite a file, or send two files to one
+/// place, before anything has moved.
+fn check_moves(root: &Path, moves: &[(PathBuf, PathBuf)]) -> Result<(), TexrsError> {
+    let leaving: BTreeSet<&PathBuf> = moves.iter().map(|(from, _)| from).collect();
+    let mut arriving = BTreeSet::new();
+    for (from, to) in moves {
+        let clash = !arriving.insert(to) || (root.join(to).exists() && !leaving.contains(to));
+        if clash {
+            return Err(TexrsError::InitError(format!(
+                "can't move {} to {}, which is taken.",
+                from.display(),
+                to.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn move_files(
+    root: &Path,
+    moves: &[(PathBuf, PathBuf)],
+    format: MessageFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut emptied = BTreeSet::new();
+    for (from, to) in moves {
+        if let Some(parent) = root.join(to).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(root.join(from), root.join(to))?;
+        emit(
+            format,
+            Event::FileMoved {
+                from: from.display().to_string(),
+                to: to.display().to_string(),
+            },
+        );
+        emptied.extend(
+            from.ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty()),
+        );
+    }
+    // Deepest first; only directories left empty are removed.
+    let mut emptied: Vec<&Path> = emptied.into_iter().collect();
+    emptied.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    for dir in emptied {
+        let _ = fs::remove_dir(root.join(dir));
+    }
+    Ok(())
+}
+
+/// Points the image & bibliography paths in the moved sources at the
+/// files' new places. Those paths were relative to the main file's
+/// directory; now they're relative to `graphics/` & `bib/`, which the
+/// build puts on the search paths.
+fn rewrite_sources(
+    root: &Path,
+    analysis: &Analysis,
+    moves: &[(PathBuf, PathBuf)],
+    format: MessageFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut references = BTreeMap::new();
+    for (from, to) in moves {
+        let new_dir = if analysis.images.contains(from) {
+            "graphics"
+        } else if analysis.bibliographies.contains(from) {
+            "bib"
+        } else {
+            continue;
+        };
+        let (Ok(old), Ok(new)) = (
+            from.strip_prefix(analysis.main_dir()),
+            to.strip_prefix(new_dir),
+        ) else {
+            continue;
+        };
+        let (old, new) = (layout_path(old), layout_path(new));
+        // `\includegraphics{fig/plot}` & `\bibliography{refs}` leave
+        // the extension off.
+        if let (Some((old_stem, _)), Some((new_stem, _))) =
+            (old.rsplit_once('.'), new.rsplit_once('.'))
+        {
+            references.insert(old_stem.to_owned(), new_stem.to_owned());
+        }
+        references.insert(old, new);
+    }
+    if references.is_empty() {
+        return Ok(());
+    }
+
+    for (from, to) in moves {
+        if !analysis.sources.contains(from) || !has_extension(to, &["tex"]) {
+            continue;
+        }
+        let path = root.join(to);
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let rewritten = rewrite_references(&source, &references);
+        if rewritten != source {
+            fs::write(&path, rewritten)?;
+            emit(
+                format,
+                Event::Info {
+                    message: format!("Rewrote the paths in {}.", to.display()),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Replaces each argument of the `PATH_COMMANDS` in `source` that's a
+/// key of `references` with its value. `\bibliography` takes a list.
+fn rewrite_references(source: &str, references: &BTreeMap<String, String>) -> String {
+    let lookup = |path: &str| {
+        let path = path.trim();
+        references
+            .get(path.strip_prefix("./").unwrap_or(path))
+            .cloned()
+    };
+
+    let mut rewritten = String::with_capacity(source.len());
+    for line in source.split_inclusive('\n') {
+        let code = compat::strip_comment(line);
+        let mut rest = code;
+        let mut out = String::new();
+        while let Some((at, command)) = PATH_COMMANDS
+            .iter()
+            .filter_map(|command| rest.find(command).map(|at| (at, *command)))
+            .min()
+        {
+            let after = at + command.len();
+            // `\bibliographystyle` isn't `\bibliography`.
+            if rest[after..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                out.push_str(&rest[..after]);
+                rest = &rest[after..];
+                continue;
+            }
+            let Some(open) = argument_start(&rest[after..]).map(|open| after + open) else {
+                out.push_str(&rest[..after]);
+                rest = &rest[after..];
+                continue;
+            };
+            let Some(close) = rest[open..].find('}').map(|close| open + close) else {
+                break;
+            };
+            out.push_str(&rest[..=open]);
+            let argument = &rest[open + 1..close];
+            let replaced: Vec<String> = if command == "\\bibliography" {
+                argument
+                    .split(',')
+                    .map(|entry| lookup(entry).unwrap_or_else(|| entry.to_owned()))
+                    .collect()
+            } else {
+                vec![lookup(argument).unwrap_or_else(|| argument.to_owned())]
+            };
+            out.push_str(&replaced.join(","));
+            rest = &rest[close..];
+        }
+        out.push_str(rest);
+        out.push_str(&line[code.len()..]);
+        rewritten.push_str(&out);
+    }
+    rewritten
+}
+
+/// Where the `{` of a command's argument is, past a `*` & any `[...]`
+/// options.
+fn argument_start(after: &str) -> Option<usize> {
+    let mut i = 0;
+    let bytes = after.as_bytes();
+    loop {
+        match bytes.get(i)? {
+            b' ' | b'\t' | b'*' => i += 1,
+            b'[' => i += after[i..].find(']')? + 1,
+            b'{' => return Some(i),
+            _ => return None,
+        }
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Gathers every file below `dir`, relative to `root`, skipping hidden
+/// files & directories & any `target/` a build left.
+fn collect(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = dir.join(&name);
+        if entry.file_type()?.is_dir() {
+            if path != Path::new("target") {
+                collect(root, &path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn references() -> BTreeMap<String, String> {
+        [
+            ("figures/plot.png", "plot.png"),
+            ("figures/plot", "plot"),
+            ("refs.bib", "refs.bib"),
+            ("refs", "refs"),
+            ("old/extra.bib", "extra.bib"),
+            ("old/extra", "extra"),
+            ("plain", "NOT-A-PATH"),
+        ]
+        .into_iter()
+        .map(|(old, new)| (old.to_owned(), new.to_owned()))
+        .collect()
+    }
+
+    #[test]
+    fn rewrites_image_paths() {
+        let source = "\\includegraphics[width=\\linewidth]{figures/plot.png}\n\
+                      \\includegraphics*{./figures/plot}\n\
+                      \\includegraphics{figures/other}\n";
+        assert_eq!(
+            rewrite_references(source, &references()),
+            "\\includegraphics[width=\\linewidth]{plot.png}\n\
+             \\includegraphics*{plot}\n\
+             \\includegraphics{figures/other}\n"
+        );
+    }
+
+    #[test]
+    fn rewrites_bibliography_lists() {
+        let source = "\\addbibresource{old/extra.bib}\n\
+                      \\bibliographystyle{plain}\n\
+                      \\bibliography{refs, old/extra,missing}\n";
+        assert_eq!(
+            rewrite_references(source, &references()),
+            "\\addbibresource{extra.bib}\n\
+             \\bibliographystyle{plain}\n\
+             \\bibliography{refs,extra,missing}\n"
+        );
+    }
+
+    #[test]
+    fn leaves_comments_alone() {
+        let source = "% \\includegraphics{figures/plot}\n\
+                      \\includegraphics{figures/plot} % was {figures/plot}\n\
+                      100\\% of \\includegraphics{figures/plot}\n";
+        assert_eq!(
+            rewrite_references(source, &references()),
+            "% \\includegraphics{figures/plot}\n\
+             \\includegraphics{plot} % was {figures/plot}\n\
+             100\\% of \\includegraphics{plot}\n"
+        );
+    }
+}
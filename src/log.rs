@@ -0,0 +1,650 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use serde_derive::Serialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// TeX hard-wraps its log at this many bytes (`max_print_line`).
+const MAX_PRINT_LINE: usize = 79;
+
+/// The kinds of problem the log parser recognizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    Error,
+    Warning,
+    OverfullBox,
+    UnderfullBox,
+    UndefinedReference,
+    UndefinedCitation,
+    MissingFile,
+}
+
+impl DiagnosticKind {
+    /// Whether this kind of diagnostic means the document is broken.
+    pub fn is_error(&self) -> bool {
+        matches!(self, DiagnosticKind::Error | DiagnosticKind::MissingFile)
+    }
+}
+
+/// A single problem reported by the driver, with the source file &
+/// line it points at when the log gives them away.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub file: Option<PathBuf>,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file.display(), line, self.message),
+            (Some(file), None) => write!(f, "{}: {}", file.display(), self.message),
+            (None, Some(line)) => write!(f, "line {}: {}", line, self.message),
+            (None, None) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// The parsed contents of a driver log: every diagnostic found, plus
+/// whether LaTeX or biblatex asked for another run.
+#[derive(Debug, Clone, Default)]
+pub struct BuildLog {
+    pub diagnostics: Vec<Diagnostic>,
+    pub rerun_requested: bool,
+    pub biber_requested: bool,
+}
+
+impl BuildLog {
+    /* -------------------------------------------------------------------- */
+    /// Reads & parses the log at `path`. Logs are not always valid UTF-8,
+    /// so they are read lossily. Returns `None` if the log can't be read.
+    ///
+    /// ## Usage
+    ///
+    /// ```rust
+    /// let log = BuildLog::read("paper/target/paper.log").unwrap_or_default();
+    /// ```
+    /* -------------------------------------------------------------------- */
+    pub fn read<P: AsRef<Path>>(path: P) -> Option<BuildLog> {
+        let bytes = fs::read(path).ok()?;
+        Some(BuildLog::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// Parses the text of a driver log.
+    pub fn parse(log: &str) -> BuildLog {
+        let lines = unwrap_lines(log);
+        let mut parsed = BuildLog::default();
+        let mut files: Vec<Option<PathBuf>> = Vec::new();
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i].as_str();
+            let current_file = files.iter().rev().flatten().next().cloned();
+
+            if let Some((file, line_no, message)) = split_file_line_error(line) {
+                // `-file-line-error` style: `./paper.tex:12: Undefined control sequence.`
+                let (kind, message) = classify_error(message);
+                parsed.diagnostics.push(Diagnostic {
+                    kind,
+                    file: Some(PathBuf::from(file)),
+                    line: Some(line_no),
+                    message,
+                });
+                i = skip_error_context(&lines, i + 1);
+                continue;
+            }
+
+            if let Some(message) = line.strip_prefix("! ") {
+                // Classic style: the line number comes a few lines later as `l.12`.
+                let (kind, message) = classify_error(message);
+                let line_no = find_error_line(&lines, i + 1);
+                parsed.diagnostics.push(Diagnostic {
+                    kind,
+                    file: current_file,
+                    line: line_no,
+                    message,
+                });
+                i = skip_error_context(&lines, i + 1);
+                continue;
+            }
+
+            if line.starts_with("Overfull \\") || line.starts_with("Underfull \\") {
+                let kind = if line.starts_with("Overfull") {
+                    DiagnosticKind::OverfullBox
+                } else {
+                    DiagnosticKind::UnderfullBox
+                };
+                parsed.diagnostics.push(Diagnostic {
+                    kind,
+                    file: current_file,
+                    line: number_after(line, "at lines ")
+                        .or_else(|| number_after(line, "at line ")),
+                    message: line.to_owned(),
+                });
+                // The offending box's contents follow, up to a blank line; they
+                // are typeset text, so any parentheses in them mean nothing.
+                i += 1;
+                while i < lines.len() && !lines[i].trim().is_empty() {
+                    i += 1;
+                }
+                continue;
+            }
+
+            if let Some((source, text)) = split_warning(line) {
+                let mut message = text.to_owned();
+                // Continuation lines are indented under `(package)`.
+                let continuation = format!("({})", source);
+                while i + 1 < lines.len() && lines[i + 1].starts_with(&continuation) {
+                    i += 1;
+                    message.push(' ');
+                    message.push_str(lines[i][continuation.len()..].trim());
+                }
+                // Requests can be split across continuation lines, as in
+                // rerunfilecheck's, so they're looked for in the whole message.
+                if message.contains("Please (re)run Biber") {
+                    parsed.biber_requested = true;
+                }
+                if requests_rerun(&message) {
+                    parsed.rerun_requested = true;
+                } else if let Some(diagnostic) = classify_warning(&message, current_file) {
+                    parsed.diagnostics.push(diagnostic);
+                }
+                i += 1;
+                continue;
+            }
+
+            if requests_rerun(line) {
+                parsed.rerun_requested = true;
+                i += 1;
+                continue;
+            }
+
+            track_files(line, &mut files);
+            i += 1;
+        }
+
+        parsed
+    }
+
+    /* -------------------------------------------------------------------- */
+    /// Resolves the relative files diagnostics point at against `dir`, the
+    /// directory the driver ran from, so they stay meaningful wherever
+    /// texrs was started. Leading `./` components are dropped on the way.
+    ///
+    /// ## Usage
+    ///
+    /// ```rust
+    /// log.resolve(&dirs.tex);
+    /// ```
+    /* -------------------------------------------------------------------- */
+    pub fn resolve(&mut self, dir: &Path) {
+        for diagnostic in &mut self.diagnostics {
+            if let Some(file) = diagnostic.file.as_mut() {
+                if file.is_relative() {
+                    *file = dir.join(
+                        file.components()
+                            .filter(|c| *c != Component::CurDir)
+                            .collect::<PathBuf>(),
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.kind.is_error())
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// How many diagnostics of the given kind were found.
+    pub fn count(&self, kind: DiagnosticKind) -> usize {
+        self.diagnostics.iter().filter(|d| d.kind == kind).count()
+    }
+}
+
+/// Whether a message asks for LaTeX to be run again.
+fn requests_rerun(text: &str) -> bool {
+    text.contains("Rerun to get")
+        || text.contains("Label(s) may have changed")
+        || text.contains("Please rerun LaTeX")
+}
+
+/// Rejoins lines that TeX broke at `max_print_line`.
+fn unwrap_lines(log: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut joining = false;
+    for raw in log.lines() {
+        if joining {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(raw);
+            }
+        } else {
+            lines.push(raw.to_owned());
+        }
+        joining = raw.len() == MAX_PRINT_LINE;
+    }
+    lines
+}
+
+/// Keeps track of which file TeX is reading. TeX prints `(path` when it
+/// opens a file & `)` when it closes one; other parentheses are pushed
+/// as `None` so that they still balance.
+fn track_files(line: &str, files: &mut Vec<Option<PathBuf>>) {
+    let mut rest = line;
+    while let Some(pos) = rest.find(['(', ')']) {
+        if rest[pos..].starts_with(')') {
+            files.pop();
+            rest = &rest[pos + 1..];
+        } else {
+            let after = &rest[pos + 1..];
+            let end = after
+                .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                .unwrap_or(after.len());
+            let token = &after[..end];
+            if looks_like_file(token) {
+                files.push(Some(PathBuf::from(token)));
+            } else {
+                files.push(None);
+            }
+            rest = &after[end..];
+        }
+    }
+}
+
+fn looks_like_file(token: &str) -> bool {
+    let name = token.rsplit('/').next().unwrap_or(token);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) => {
+            !stem.is_empty() && !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        None => false,
+    }
+}
+
+/// Splits `file:line: message`, the format used with `-file-line-error`.
+fn split_file_line_error(line: &str) -> Option<(&str, u32, &str)> {
+    let mut parts = line.splitn(3, ':');
+    let file = parts.next()?;
+    let line_no = parts.next()?.parse().ok()?;
+    let message = parts.next()?.strip_prefix(' ')?;
+    if looks_like_file(file) {
+        Some((file, line_no, message))
+    } else {
+        None
+    }
+}
+
+/// Errors are followed by context lines; the `l.12 ...` line carries the
+/// line number.
+fn find_error_line(lines: &[String], start: usize) -> Option<u32> {
+    lines
+        .iter()
+        .skip(start)
+        .take(12)
+        .take_while(|l| !starts_error(l))
+        .find_map(|l| l.strip_prefix("l.").and_then(leading_number))
+}
+
+/// Whether `line` starts a new error, in either style.
+fn starts_error(line: &str) -> bool {
+    line.starts_with("! ") || split_file_line_error(line).is_some()
+}
+
+/// Skips past an error's context, which ends with the `l.12 ...` line &
+/// the line after it (the rest of the offending source line).
+fn skip_error_context(lines: &[String], start: usize) -> usize {
+    for (offset, line) in lines.iter().skip(start).take(12).enumerate() {
+        if starts_error(line) {
+            return start + offset;
+        }
+        if line.starts_with("l.") && line[2..].starts_with(|c: char| c.is_ascii_digit()) {
+            return start + offset + 2;
+        }
+    }
+    start
+}
+
+fn classify_error(message: &str) -> (DiagnosticKind, String) {
+    let message = message.trim().to_owned();
+    if message.contains("not found") && message.contains("File `") {
+        (DiagnosticKind::MissingFile, message)
+    } else {
+        (DiagnosticKind::Error, message)
+    }
+}
+
+/// Splits `LaTeX Warning: ...`, `Package foo Warning: ...` & `Class foo
+/// Warning: ...` into their source (`LaTeX`, `foo`) & text.
+fn split_warning(line: &str) -> Option<(&str, &str)> {
+    let (head, text) = line.split_once(" Warning: ")?;
+    let source = if let Some(package) = head.strip_prefix("Package ") {
+        package
+    } else if let Some(class) = head.strip_prefix("Class ") {
+        class
+    } else if let Some(subsystem) = head.strip_prefix("LaTeX ") {
+        // `LaTeX Font Warning` continues on lines starting with `(Font)`.
+        subsystem
+    } else if head == "LaTeX" || head == "pdfTeX" {
+        head
+    } else {
+        return None;
+    };
+    Some((source, text.trim()))
+}
+
+fn classify_warning(message: &str, file: Option<PathBuf>) -> Option<Diagnostic> {
+    // Summaries repeat what the individual warnings already said, & rerun
+    // requests are acted on by the build rather than reported.
+    if message.starts_with("Please (re)run Biber")
+        || message.starts_with("There were undefined references")
+        || message.starts_with("There were undefined citations")
+        || message.starts_with("There were multiply-defined labels")
+    {
+        return None;
+    }
+
+    // The kernel quotes labels as `label', biblatex as 'key'.
+    let kind = if message.starts_with("Reference ") && message.contains("undefined") {
+        DiagnosticKind::UndefinedReference
+    } else if message.starts_with("Citation ") && message.contains("undefined") {
+        DiagnosticKind::UndefinedCitation
+    } else if message.starts_with("File `") && message.contains("not found") {
+        DiagnosticKind::MissingFile
+    } else {
+        DiagnosticKind::Warning
+    };
+
+    Some(Diagnostic {
+        kind,
+        file,
+        line: number_after(message, "on input line "),
+        message: message.trim_end_matches('.').to_owned(),
+    })
+}
+
+fn number_after(text: &str, marker: &str) -> Option<u32> {
+    let pos = text.find(marker)?;
+    leading_number(&text[pos + marker.len()..])
+}
+
+fn leading_number(text: &str) -> Option<u32> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn only(log: &BuildLog, kind: DiagnosticKind) -> Vec<&Diagnostic> {
+        log.diagnostics.iter().filter(|d| d.kind == kind).collect()
+    }
+
+    #[test]
+    fn file_line_errors() {
+        let log = BuildLog::parse(
+            r"This is pdfTeX, Version 3.141592653-2.6-1.40.25 (TeX Live 2023) (preloaded format=pdflatex)
+ restricted \write18 enabled.
+entering extended mode
+(./paper.tex
+LaTeX2e <2023-11-01> patch level 1
+./paper.tex:12: Undefined control sequence.
+l.12 \foo
+         
+./paper.tex:14: LaTeX Error: File `missing.sty' not found.
+
+Type X to quit or <RETURN> to proceed,
+or enter new name. (Default extension: sty)
+
+Enter file name: 
+./paper.tex:14: Emergency stop.
+<read *> 
+         
+l.14 \usepackage
+                {missing}^^M
+)
+",
+        );
+        let errors: Vec<_> = log.errors().collect();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].kind, DiagnosticKind::Error);
+        assert_eq!(errors[0].file, Some(PathBuf::from("./paper.tex")));
+        assert_eq!(errors[0].line, Some(12));
+        assert_eq!(errors[0].message, "Undefined control sequence.");
+        assert_eq!(errors[1].kind, DiagnosticKind::MissingFile);
+        assert_eq!(errors[1].line, Some(14));
+        assert_eq!(errors[2].message, "Emergency stop.");
+    }
+
+    #[test]
+    fn classic_errors() {
+        let log = BuildLog::parse(
+            r#"This is XeTeX, Version 3.141592653-2.6-0.999995 (TeX Live 2023) (preloaded format=xelatex)
+entering extended mode
+(./paper.tex
+LaTeX2e <2023-11-01> patch level 1
+! Package fontspec Error: The font "Garamond Premier Pro" cannot be found.
+
+For immediate help type H <return>.
+ ...                                              
+                                                  
+l.8 \setmainfont{Garamond Premier Pro}
+                                      
+! Undefined control sequence.
+l.21 \foo
+         
+)
+"#,
+        );
+        let errors: Vec<_> = log.errors().collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].message,
+            r#"Package fontspec Error: The font "Garamond Premier Pro" cannot be found."#
+        );
+        assert_eq!(errors[0].file, Some(PathBuf::from("./paper.tex")));
+        assert_eq!(errors[0].line, Some(8));
+        assert_eq!(errors[1].message, "Undefined control sequence.");
+        assert_eq!(errors[1].line, Some(21));
+    }
+
+    #[test]
+    fn wrapped_lines() {
+        let log = BuildLog::parse(
+            r"(./paper.tex (/usr/local/texlive/2023/texmf-dist/tex/latex/l3backend/l3backend-
+xetex.def
+File: l3backend-xetex.def 2023-11-09 L3 backend support: XeTeX
+)
+LaTeX Warning: Reference `sec:a-rather-long-label-for-the-related-work-section'
+ on page 3 undefined on input line 42.
+
+)
+",
+        );
+        let references = only(&log, DiagnosticKind::UndefinedReference);
+        assert_eq!(references.len(), 1);
+        assert_eq!(
+            references[0].message,
+            "Reference `sec:a-rather-long-label-for-the-related-work-section' on page 3 undefined on input line 42"
+        );
+        assert_eq!(references[0].line, Some(42));
+        // The wrapped path was closed again, so the warning is in the document.
+        assert_eq!(references[0].file, Some(PathBuf::from("./paper.tex")));
+    }
+
+    #[test]
+    fn nested_files() {
+        let log = BuildLog::parse(
+            r"(./thesis.tex
+LaTeX2e <2023-11-01> patch level 1
+(/usr/local/texlive/2023/texmf-dist/tex/latex/base/report.cls
+Document Class: report 2023/05/17 v1.4n Standard LaTeX document class
+(/usr/local/texlive/2023/texmf-dist/tex/latex/base/size12.clo
+File: size12.clo 2023/05/17 v1.4n Standard LaTeX file (size option)
+))
+(./chapters/introduction.tex
+Chapter 1.
+! Undefined control sequence.
+l.3 \foo
+        
+) [1] (./chapters/background.tex [2]) [3]
+! Undefined control sequence.
+l.20 \bar
+         
+)
+",
+        );
+        let errors: Vec<_> = log.errors().collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].file,
+            Some(PathBuf::from("./chapters/introduction.tex"))
+        );
+        assert_eq!(errors[0].line, Some(3));
+        assert_eq!(errors[1].file, Some(PathBuf::from("./thesis.tex")));
+        assert_eq!(errors[1].line, Some(20));
+    }
+
+    #[test]
+    fn overfull_box_contents() {
+        let log = BuildLog::parse(
+            r"(./paper.tex
+Overfull \hbox (15.60004pt too wide) in paragraph at lines 10--12
+[]\T1/cmr/m/n/10 The first case (where $x$ is pos-i-tive, see
+ []
+
+Underfull \hbox (badness 10000) in paragraph at lines 30--30
+
+ []
+
+! Undefined control sequence.
+l.35 \foo
+         
+)
+",
+        );
+        let overfull = only(&log, DiagnosticKind::OverfullBox);
+        assert_eq!(overfull.len(), 1);
+        assert_eq!(overfull[0].line, Some(10));
+        assert_eq!(overfull[0].file, Some(PathBuf::from("./paper.tex")));
+        let underfull = only(&log, DiagnosticKind::UnderfullBox);
+        assert_eq!(underfull.len(), 1);
+        assert_eq!(underfull[0].line, Some(30));
+        // The unbalanced parenthesis in the box didn't open a file.
+        let errors: Vec<_> = log.errors().collect();
+        assert_eq!(errors[0].file, Some(PathBuf::from("./paper.tex")));
+        assert_eq!(errors[0].line, Some(35));
+    }
+
+    #[test]
+    fn multi_line_biblatex_warnings() {
+        let log = BuildLog::parse(
+            r"(./paper.tex
+Package biblatex Warning: The following entry could not be found
+(biblatex)                in the database:
+(biblatex)                knuth1984
+(biblatex)                Please verify the spelling and rerun
+(biblatex)                LaTeX afterwards.
+
+LaTeX Warning: Citation 'knuth1984' on page 1 undefined on input line 9.
+
+)
+",
+        );
+        let warnings = only(&log, DiagnosticKind::Warning);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "The following entry could not be found in the database: knuth1984 Please verify the spelling and rerun LaTeX afterwards"
+        );
+        assert_eq!(warnings[0].file, Some(PathBuf::from("./paper.tex")));
+        let citations = only(&log, DiagnosticKind::UndefinedCitation);
+        assert_eq!(citations.len(), 1);
+        assert_eq!(citations[0].line, Some(9));
+        assert!(!log.biber_requested);
+    }
+
+    #[test]
+    fn rerun_requests() {
+        let log = BuildLog::parse(
+            r"(./paper.tex
+Package biblatex Warning: Please (re)run Biber on the file:
+(biblatex)                paper
+(biblatex)                and rerun LaTeX afterwards.
+
+LaTeX Warning: There were undefined references.
+
+LaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.
+
+)
+",
+        );
+        assert!(log.biber_requested);
+        assert!(log.rerun_requested);
+        // Both are acted on by the build rather than reported.
+        assert!(log.diagnostics.is_empty());
+
+        let log = BuildLog::parse(
+            r"(./paper.tex
+Package rerunfilecheck Warning: File `paper.out' has changed.
+(rerunfilecheck)                Rerun to get outlines right
+(rerunfilecheck)                or use package `bookmark'.
+
+)
+",
+        );
+        assert!(log.rerun_requested);
+        assert!(log.diagnostics.is_empty());
+
+        let log = BuildLog::parse("(./paper.tex\n[1] (./paper.aux) )\n");
+        assert!(!log.biber_requested);
+        assert!(!log.rerun_requested);
+    }
+
+    #[test]
+    fn resolve_relative_files() {
+        let mut log = BuildLog::parse(
+            r"(./chapters/intro.tex
+! Undefined control sequence.
+l.3 \foo
+        
+)
+/project/graphics/plot.tex:5: Undefined control sequence.
+l.5 \bar
+        
+",
+        );
+        log.resolve(Path::new("/project/tex"));
+        assert_eq!(
+            log.diagnostics[0].file,
+            Some(PathBuf::from("/project/tex/chapters/intro.tex"))
+        );
+        assert_eq!(
+            log.diagnostics[1].file,
+            Some(PathBuf::from("/project/graphics/plot.tex"))
+        );
+    }
+}
@@ -19,13 +19,28 @@
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand, ValueEnum};
+use message::{emit, Event, MessageFormat};
 use serde_derive::{Deserialize, Serialize};
 
 /* MODULES */
 pub mod build;
+pub mod chapter;
+pub mod clean;
 pub mod cli;
+pub mod compat;
 pub mod config;
+pub mod driver;
+pub mod init;
+pub mod log;
+pub mod math;
+pub mod message;
 pub mod new;
+pub mod presentation;
+pub mod registry;
+pub mod template;
+pub mod thesis;
+pub mod vcs;
+pub mod watch;
 
 #[derive(Debug, Parser)] // requires `derive` feature
 #[command(name = "texrs")]
@@ -40,25 +55,86 @@ enum Commands {
     /// Create a new LaTeX project.
     #[command(arg_required_else_help = true)]
     New {
-        #[arg(
-            long,
-            require_equals = true,
-            value_name = "WHEN",
-            num_args = 0..=1,
-            default_value_t = DocumentType::Article,
-            default_missing_value = "always",
-            value_enum
-        )]
-        /// Type of template.
-        template: DocumentType,
         /// Project name.
         name: String,
+        #[command(flatten)]
+        options: cli::NewOptions,
+        /// Create the project even if the driver can't compile the
+        /// template's preamble.
+        #[arg(long)]
+        force: bool,
+        /// Output style: colored text, or one JSON event per line.
+        #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
+    },
+    /// Adopt an existing LaTeX directory as a project.
+    Init {
+        /// The directory to adopt. Defaults to the current directory.
+        path: Option<PathBuf>,
+        /// The main .tex file, if more than one has a \documentclass.
+        #[arg(long, value_name = "FILE")]
+        main: Option<PathBuf>,
+        /// Driver to build with, in place of the one inferred.
+        #[arg(long)]
+        driver: Option<String>,
+        /// Move the files into tex/, bib/ & graphics/, rewriting the
+        /// paths that reference them.
+        #[arg(long = "move")]
+        move_files: bool,
+        /// Show what would be done without changing anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Output style: colored text, or one JSON event per line.
+        #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
     },
     /// Build an existing project.
-    #[command(arg_required_else_help = true)]
     Build {
-        /// Path to the configuration file.
-        path: PathBuf,
+        /// The project's config file, or any path inside the project.
+        /// Defaults to the current directory.
+        path: Option<PathBuf>,
+        /// Build only this variant of a presentation.
+        #[arg(long, value_enum)]
+        variant: Option<presentation::Variant>,
+        /// Build only these chapters of a book or thesis, by number, slug
+        /// or file name. References to other chapters use the last full
+        /// build. Can't be combined with --variant.
+        #[arg(long, value_name = "CHAPTER", num_args = 1.., conflicts_with = "variant")]
+        only: Vec<String>,
+        /// Output style: colored text, or one JSON event per line.
+        #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
+    },
+    /// Remove build artifacts from a project's target dir.
+    Clean {
+        /// The project's config file, or any path inside the project.
+        /// Defaults to the current directory.
+        path: Option<PathBuf>,
+        /// Keep the built PDFs.
+        #[arg(long)]
+        keep_pdf: bool,
+        /// List what would be removed without removing anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove everything in the target dir, generated assets included.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Rebuild a project whenever its sources change.
+    Watch {
+        /// The project's config file, or any path inside the project.
+        /// Defaults to the current directory.
+        path: Option<PathBuf>,
+    },
+    /// Add, remove, move & list the chapters of a book or thesis.
+    Chapter {
+        #[command(subcommand)]
+        command: ChapterCommand,
+    },
+    /// List, show & check templates.
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommand,
     },
     /// Interactive project setup. Recommended.
     #[command()]
@@ -66,10 +142,57 @@ enum Commands {
         /// Project name.
         #[arg(value_name = "NAME")]
         name: Option<String>,
+        #[command(flatten)]
+        options: cli::NewOptions,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ChapterCommand {
+    /// List the chapters in order.
+    List,
+    /// Add a chapter, at the end unless a position is given.
+    Add {
+        /// Chapter title; its slug names the file.
+        title: String,
+        /// Position to insert the chapter at, counting from 1.
+        #[arg(long)]
+        at: Option<usize>,
+    },
+    /// Remove a chapter & delete its file.
+    Remove {
+        /// Chapter number, slug, or file name.
+        chapter: String,
+        /// Keep the file, unnumbered, in `tex/chapters/`.
+        #[arg(long)]
+        keep_file: bool,
+    },
+    /// Move a chapter to another position.
+    Move {
+        /// Chapter number, slug, or file name.
+        chapter: String,
+        /// New position, counting from 1.
+        position: usize,
     },
 }
 
-#[derive(ValueEnum, Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Subcommand)]
+enum TemplatesCommand {
+    /// List every built-in & user template.
+    List,
+    /// Print the files a template creates.
+    Show {
+        /// Template name.
+        name: String,
+    },
+    /// Check that a template renders & suits its driver.
+    Check {
+        /// Template name.
+        name: String,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum DocumentType {
     Article,
     Book,
@@ -81,10 +204,75 @@ pub enum DocumentType {
     Recipe,
 }
 
+/// Everything that can go wrong in texrs. Each variant exits the process
+/// with its own code (see `exit_code`), so scripts can tell them apart.
 #[derive(Debug)]
 pub enum TexrsError {
     InvalidChoice(String),
     IoError(std::io::Error),
+    Cancelled,
+    /// No `config.toml` at, or above, the given path.
+    MissingConfig(PathBuf),
+    /// `config.toml` isn't valid; `line` & `column` are 1-based.
+    ConfigParse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        snippet: String,
+        message: String,
+    },
+    /// The named driver or tool isn't installed or isn't on the `PATH`.
+    DriverNotInstalled(String),
+    /// The driver ran but exited unsuccessfully.
+    DriverFailed {
+        driver: String,
+        code: Option<i32>,
+    },
+    /// The driver exited successfully, but its log reported this many errors.
+    CompileFailed(usize),
+    /// Biber ran but exited unsuccessfully; holds its error output.
+    BiberFailed(String),
+    /// Makeindex ran but exited unsuccessfully; holds its error output.
+    MakeindexFailed(String),
+    /// A git step of `new` failed; holds the step & git's error output.
+    GitFailed {
+        step: String,
+        stderr: String,
+    },
+    TemplateError(String),
+    /// A chapter command couldn't find, or would clash with, a chapter.
+    ChapterError(String),
+    /// `init` couldn't make sense of, or refused to touch, a directory.
+    InitError(String),
+    /// The driver can't compile the document's preamble.
+    IncompatibleDriver {
+        driver: String,
+        issue: String,
+        suggested: Option<String>,
+    },
+}
+
+impl TexrsError {
+    /// The process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TexrsError::InvalidChoice(_) => 2,
+            TexrsError::IoError(_) => 3,
+            TexrsError::MissingConfig(_) => 4,
+            TexrsError::ConfigParse { .. } => 5,
+            TexrsError::DriverNotInstalled(_) => 6,
+            TexrsError::DriverFailed { .. } => 7,
+            TexrsError::BiberFailed(_) => 8,
+            TexrsError::TemplateError(_) => 9,
+            TexrsError::IncompatibleDriver { .. } => 10,
+            TexrsError::ChapterError(_) => 11,
+            TexrsError::InitError(_) => 12,
+            TexrsError::CompileFailed(_) => 13,
+            TexrsError::GitFailed { .. } => 14,
+            TexrsError::MakeindexFailed(_) => 15,
+            TexrsError::Cancelled => 130,
+        }
+    }
 }
 
 impl std::fmt::Display for TexrsError {
@@ -92,130 +280,231 @@ impl std::fmt::Display for TexrsError {
         match self {
             TexrsError::IoError(e) => e.fmt(f),
             TexrsError::InvalidChoice(msg) => write!(f, "Invalid input: `{}` not permitted.", msg),
+            TexrsError::Cancelled => write!(f, "Build cancelled."),
+            TexrsError::MissingConfig(path) => write!(
+                f,
+                "No {} found at `{}` or in any directory above it.",
+                config::CONFIG_FILE,
+                path.display()
+            ),
+            TexrsError::ConfigParse {
+                path,
+                line,
+                column,
+                snippet,
+                message,
+            } => {
+                writeln!(
+                    f,
+                    "{}:{}:{}: {}",
+                    path.display(),
+                    line,
+                    column,
+                    message.trim()
+                )?;
+                let gutter = line.to_string().len();
+                writeln!(f, "{:>gutter$} |", "")?;
+                writeln!(f, "{} | {}", line, snippet)?;
+                write!(f, "{:>gutter$} | {:>column$}", "", "^")
+            }
+            TexrsError::DriverNotInstalled(driver) => {
+                write!(f, "`{}` is not installed, or is not on the PATH.", driver)
+            }
+            TexrsError::DriverFailed {
+                driver,
+                code: Some(code),
+            } => write!(f, "{} failed with exit code {}.", driver, code),
+            TexrsError::DriverFailed { driver, code: None } => {
+                write!(f, "{} was terminated by a signal.", driver)
+            }
+            TexrsError::CompileFailed(errors) => {
+                write!(f, "The driver's log reported {} error(s).", errors)
+            }
+            TexrsError::BiberFailed(stderr) => write!(f, "Biber failed: {}", stderr.trim()),
+            TexrsError::MakeindexFailed(stderr) => {
+                write!(f, "Makeindex failed: {}", stderr.trim())
+            }
+            TexrsError::GitFailed { step, stderr } => {
+                write!(f, "Git {} failed: {}", step, stderr.trim())
+            }
+            TexrsError::TemplateError(msg) => write!(f, "Template error: {}", msg),
+            TexrsError::ChapterError(msg) => write!(f, "Chapter error: {}", msg),
+            TexrsError::InitError(msg) => write!(f, "Init error: {}", msg),
+            TexrsError::IncompatibleDriver {
+                driver,
+                issue,
+                suggested,
+            } => {
+                write!(f, "`{}` can't compile this document: {}", driver, issue)?;
+                match suggested {
+                    Some(suggested) => write!(f, " Use `{}` instead, or --force.", suggested),
+                    None => write!(f, " Use --force to go ahead anyway."),
+                }
+            }
         }
     }
 }
 
 impl std::error::Error for TexrsError {}
 
+/// The exit code for any error: `TexrsError`s carry their own, plain IO
+/// errors share `IoError`'s, & everything else exits with 1.
+fn exit_code(err: &(dyn std::error::Error + 'static)) -> i32 {
+    if let Some(err) = err.downcast_ref::<TexrsError>() {
+        err.exit_code()
+    } else if err.is::<std::io::Error>() {
+        TexrsError::IoError(std::io::Error::other("")).exit_code()
+    } else {
+        1
+    }
+}
+
+/// Reports `err` in the given format & exits with its exit code.
+fn fail(format: MessageFormat, err: Box<dyn std::error::Error>) -> ! {
+    let code = exit_code(err.as_ref());
+    emit(
+        format,
+        Event::Error {
+            message: err.to_string(),
+        },
+    );
+    std::process::exit(code);
+}
+
 fn main() {
     let args = Cli::parse();
 
-    let mut config = config::ProjectConfig::new();
-
     match args.command {
-        Commands::New { template, name } => match template {
-            DocumentType::Article => {
-                config.set_name(&name);
-                config.set_driver("pdflatex");
-                config.set_citations(true);
-                config.set_graphics(true);
-                config.set_doctype(DocumentType::Article);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
-                }
+        Commands::New {
+            name,
+            options,
+            force,
+            message_format,
+        } => {
+            config::load_user_config().unwrap_or_else(|err| fail(message_format, err));
+            let config = cli::options_config(&name, &options)
+                .unwrap_or_else(|err| fail(message_format, Box::new(err)));
+            let root = options.root(&config);
+            if let Err(err) = new::create_directories(config, &root, force, message_format) {
+                fail(message_format, err);
             }
-            DocumentType::Book => {
-                config.set_name(&name);
-                config.set_driver("xelatex");
-                config.set_citations(true);
-                config.set_graphics(true);
-                config.set_doctype(DocumentType::Book);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
-                }
+        }
+        Commands::Init {
+            path,
+            main,
+            driver,
+            move_files,
+            dry_run,
+            message_format,
+        } => {
+            let root = path.unwrap_or_else(|| PathBuf::from("."));
+            let options = init::InitOptions {
+                main,
+                driver,
+                move_files,
+                dry_run,
+                format: message_format,
+            };
+            if let Err(err) = init::init_project(&root, &options) {
+                fail(message_format, err);
             }
-            DocumentType::Thesis => {
-                config.set_name(&name);
-                config.set_driver("xelatex");
-                config.set_citations(true);
-                config.set_graphics(true);
-                config.set_doctype(DocumentType::Thesis);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
-                }
+        }
+        Commands::Build {
+            path,
+            variant,
+            only,
+            message_format,
+        } => {
+            let start = path.unwrap_or_else(|| PathBuf::from("."));
+            let (root, config) =
+                config::find_project(&start).unwrap_or_else(|err| fail(message_format, err));
+            let options = build::BuildOptions {
+                format: message_format,
+                variant,
+                only,
+                ..Default::default()
+            };
+            if let Err(err) = build::build_project(&root, config, &options) {
+                fail(message_format, err);
             }
-            DocumentType::Presentation => {
-                config.set_name(&name);
-                config.set_driver("xelatex");
-                config.set_citations(true);
-                config.set_graphics(true);
-                config.set_doctype(DocumentType::Presentation);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
-                }
+        }
+        Commands::Clean {
+            path,
+            keep_pdf,
+            dry_run,
+            all,
+        } => {
+            let start = path.unwrap_or_else(|| PathBuf::from("."));
+            let (root, _) =
+                config::find_project(&start).unwrap_or_else(|err| fail(MessageFormat::Human, err));
+            let options = clean::CleanOptions {
+                keep_pdf,
+                dry_run,
+                all,
+            };
+            if let Err(err) = clean::clean_project(&root, options) {
+                fail(MessageFormat::Human, err);
             }
-            DocumentType::MathArticle => {
-                config.set_name(&name);
-                config.set_driver("xelatex");
-                config.set_citations(true);
-                config.set_graphics(true);
-                config.set_doctype(DocumentType::MathArticle);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
-                }
+        }
+        Commands::Watch { path } => {
+            let start = path.unwrap_or_else(|| PathBuf::from("."));
+            let (root, config) =
+                config::find_project(&start).unwrap_or_else(|err| fail(MessageFormat::Human, err));
+            if let Err(err) = watch::watch_project(&root, config) {
+                fail(MessageFormat::Human, err);
             }
-            DocumentType::Notes => {
-                config.set_name(&name);
-                config.set_driver("pdflatex");
-                config.set_citations(false);
-                config.set_graphics(false);
-                config.set_doctype(DocumentType::Notes);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
+        }
+        Commands::Chapter { command } => {
+            let (root, config) = config::find_project(&PathBuf::from("."))
+                .unwrap_or_else(|err| fail(MessageFormat::Human, err));
+            let result = chapter::Book::open(&root, &config).and_then(|mut book| match command {
+                ChapterCommand::List => {
+                    chapter::list_chapters(&book);
+                    Ok(())
                 }
-            }
-            DocumentType::Letter => {
-                config.set_name(&name);
-                config.set_driver("xelatex");
-                config.set_citations(false);
-                config.set_graphics(true);
-                config.set_doctype(DocumentType::Letter);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
+                ChapterCommand::Add { title, at } => chapter::add_chapter(&mut book, &title, at),
+                ChapterCommand::Remove { chapter, keep_file } => {
+                    chapter::remove_chapter(&mut book, &chapter, keep_file)
                 }
-            }
-            DocumentType::Recipe => {
-                config.set_name(&name);
-                config.set_driver("xelatex");
-                config.set_citations(false);
-                config.set_graphics(true);
-                config.set_doctype(DocumentType::Recipe);
-                match new::create_directories(config) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{}", err),
+                ChapterCommand::Move { chapter, position } => {
+                    chapter::move_chapter(&mut book, &chapter, position)
                 }
-            }
-        },
-        Commands::Build { path } => {
-            let config = build::read_config(path).unwrap();
-            match build::build_project(config) {
-                Ok(_) => println!("Success!"),
-                Err(err) => eprintln!("{}", err),
+            });
+            if let Err(err) = result {
+                fail(MessageFormat::Human, err);
             }
         }
-        Commands::Interactive { name } => {
-            if let Some(project_name) = name {
-                match cli::config_menu(&project_name) {
-                    Ok(new_config) => config = new_config,
-                    Err(err) => eprintln!("{}", err),
-                }
-            } else {
-                match cli::config_menu_nameless() {
-                    Ok(new_config) => config = new_config,
-                    Err(err) => eprintln!("{}", err),
+        Commands::Templates { command } => {
+            config::load_user_config().unwrap_or_else(|err| fail(MessageFormat::Human, err));
+            let registry = registry::Registry::discover();
+            let result = match command {
+                TemplatesCommand::List => {
+                    registry::list_templates(&registry);
+                    Ok(())
                 }
+                TemplatesCommand::Show { name } => registry
+                    .find(&name)
+                    .map_err(|err| err.into())
+                    .and_then(registry::show_template),
+                TemplatesCommand::Check { name } => registry
+                    .find(&name)
+                    .map_err(|err| err.into())
+                    .and_then(registry::check_template),
+            };
+            if let Err(err) = result {
+                fail(MessageFormat::Human, err);
+            }
+        }
+        Commands::Interactive { name, options } => {
+            config::load_user_config().unwrap_or_else(|err| fail(MessageFormat::Human, err));
+            let mut config = cli::config_menu(name.as_deref(), &options)
+                .unwrap_or_else(|err| fail(MessageFormat::Human, err));
+            if let Err(err) = cli::confirm_driver(&mut config) {
+                fail(MessageFormat::Human, err);
             }
-            match new::create_directories(config) {
-                Ok(_) => {}
-                Err(err) => eprintln!("{}", err),
+            let root = options.root(&config);
+            if let Err(err) = new::create_directories(config, &root, false, MessageFormat::Human) {
+                fail(MessageFormat::Human, err);
             }
         }
     }
@@ -0,0 +1,100 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::build::write_if_changed;
+use crate::compat;
+use crate::config::*;
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Where the generated theorem setup goes, relative to `tex/`.
+pub const SETUP_FILE: &str = "theorems.tex";
+
+/// The environments a math article gets: name, heading & amsthm style.
+/// The first is the one the others share a counter with.
+const ENVIRONMENTS: [(&str, &str, &str); 7] = [
+    ("theorem", "Theorem", "plain"),
+    ("lemma", "Lemma", "plain"),
+    ("proposition", "Proposition", "plain"),
+    ("corollary", "Corollary", "plain"),
+    ("definition", "Definition", "definition"),
+    ("example", "Example", "definition"),
+    ("remark", "Remark", "remark"),
+];
+
+/* -------------------------------------------------------------------- */
+/// The source of `theorems.tex`: `unicode-math` if the config asks for
+/// it & the driver's engine can load it, then the theorem environments,
+/// numbered as the `[math]` table says. `proof` comes from amsthm.
+/* -------------------------------------------------------------------- */
+pub fn setup(config: &ProjectConfig) -> String {
+    let math = config.get_math();
+    let within = match math.get_numbering() {
+        Numbering::Section => "[section]",
+        Numbering::Global => "",
+    };
+
+    let mut source = String::from(
+        "% Generated by texrs from the [math] table in config.toml, &\n\
+         % rewritten on every build. Edit that instead.\n",
+    );
+    if math.get_unicode_math() && compat::engine(config).is_unicode() {
+        source.push_str("\\usepackage{unicode-math}\n");
+    }
+
+    let (counter, _, _) = ENVIRONMENTS[0];
+    let mut style = "";
+    for (i, (name, heading, env_style)) in ENVIRONMENTS.iter().enumerate() {
+        if *env_style != style {
+            style = env_style;
+            source.push_str(&format!("\\theoremstyle{{{}}}\n", style));
+        }
+        if i > 0 && math.get_shared_counter() {
+            source.push_str(&format!(
+                "\\newtheorem{{{}}}[{}]{{{}}}\n",
+                name, counter, heading
+            ));
+        } else {
+            source.push_str(&format!(
+                "\\newtheorem{{{}}}{{{}}}{}\n",
+                name, heading, within
+            ));
+        }
+    }
+    if math.get_numbering() == Numbering::Section {
+        source.push_str("\\numberwithin{equation}{section}\n");
+    }
+    source
+}
+
+/* -------------------------------------------------------------------- */
+/// Writes the theorem setup into `tex/`, leaving the file alone if it's
+/// already up to date so that `watch` doesn't rebuild for it. Returns
+/// the file's path, & whether it was written.
+///
+/// ## Usage
+///
+/// ```rust
+/// math::write_setup(&config, &dirs.tex)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn write_setup(config: &ProjectConfig, tex: &Path) -> Result<(PathBuf, bool), Box<dyn Error>> {
+    let path = tex.join(SETUP_FILE);
+    let written = write_if_changed(&path, &setup(config))?;
+    Ok((path, written))
+}
@@ -0,0 +1,309 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::log::*;
+
+use clap::ValueEnum;
+use colored::*;
+use serde_derive::Serialize;
+
+/// How texrs reports what it's doing: colored text for people, or one
+/// JSON object per line for editor plugins & scripts.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+/// Everything `new` & `build` report. In JSON each event is tagged with
+/// an `event` field holding the variant name in kebab-case, e.g.
+/// `{"event":"pass-finished","pass":1,"driver":"pdflatex","success":true}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    TargetDir {
+        path: String,
+        created: bool,
+    },
+    /// A build of several jobs, such as presentation variants, moves on
+    /// to the next one.
+    JobStarted {
+        jobname: String,
+    },
+    PassStarted {
+        pass: u32,
+        driver: String,
+    },
+    PassFinished {
+        pass: u32,
+        driver: String,
+        success: bool,
+    },
+    BiberRun {
+        pass: u32,
+        success: bool,
+        stderr: String,
+    },
+    MakeindexRun {
+        pass: u32,
+        success: bool,
+        stderr: String,
+    },
+    Diagnostic(Diagnostic),
+    Artifact {
+        path: String,
+    },
+    BuildFinished {
+        success: bool,
+        passes: u32,
+        stable: bool,
+        errors: usize,
+        warnings: usize,
+        undefined_references: usize,
+        undefined_citations: usize,
+        bad_boxes: usize,
+    },
+    FileCreated {
+        path: String,
+    },
+    Skipped {
+        path: String,
+    },
+    FileMoved {
+        from: String,
+        to: String,
+    },
+    Git {
+        step: String,
+        success: bool,
+    },
+    /// A package the driver can't, or shouldn't, load.
+    Incompatibility {
+        driver: String,
+        package: String,
+        message: String,
+        suggested_driver: Option<String>,
+    },
+    Info {
+        message: String,
+    },
+    Warning {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl Event {
+    /// Tallies a parsed log into a `BuildFinished` event; `tools_succeeded`
+    /// is false if the driver, biber or makeindex failed.
+    pub fn build_finished(
+        log: &BuildLog,
+        passes: u32,
+        stable: bool,
+        tools_succeeded: bool,
+    ) -> Event {
+        let errors = log.errors().count();
+        Event::BuildFinished {
+            success: tools_succeeded && errors == 0,
+            passes,
+            stable,
+            errors,
+            warnings: log.diagnostics.len() - errors,
+            undefined_references: log.count(DiagnosticKind::UndefinedReference),
+            undefined_citations: log.count(DiagnosticKind::UndefinedCitation),
+            bad_boxes: log.count(DiagnosticKind::OverfullBox)
+                + log.count(DiagnosticKind::UnderfullBox),
+        }
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// Reports an event in the chosen format.
+///
+/// ## Usage
+///
+/// ```rust
+/// emit(format, Event::Artifact { path: "paper/target/paper.pdf".to_owned() });
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn emit(format: MessageFormat, event: Event) {
+    match format {
+        MessageFormat::Json => match serde_json::to_string(&event) {
+            Ok(line) => println!("{}", line),
+            Err(err) => eprintln!("{}", err),
+        },
+        MessageFormat::Human => print_human(event),
+    }
+}
+
+fn print_human(event: Event) {
+    match event {
+        Event::TargetDir { created: true, .. } => {
+            println!("[  {}  ] Creating target dir.", "OK".green())
+        }
+        Event::TargetDir { created: false, .. } => {
+            println!("[  {}  ] Target dir. exists; skipping!", "OK".green())
+        }
+        Event::JobStarted { jobname } => {
+            println!(
+                "[ {} ] Building {}.",
+                "INFO".blue(),
+                jobname.as_str().blue()
+            )
+        }
+        Event::PassStarted { .. } => {}
+        Event::PassFinished {
+            pass,
+            driver,
+            success: true,
+        } => println!(
+            "[  {}  ] Ran {} on pass {}.",
+            "OK".green(),
+            driver.as_str().blue(),
+            pass.to_string().as_str().blue()
+        ),
+        Event::PassFinished {
+            pass,
+            driver,
+            success: false,
+        } => println!(
+            "[ {} ] {} failed on pass {}.",
+            "FAIL".red(),
+            driver.as_str().blue(),
+            pass.to_string().as_str().blue()
+        ),
+        Event::BiberRun {
+            pass,
+            success: true,
+            ..
+        } => println!(
+            "[  {}  ] Ran biber on pass {}.",
+            "OK".green(),
+            pass.to_string().as_str().blue()
+        ),
+        // Its output is printed once, by the error that ends the build.
+        Event::BiberRun {
+            pass,
+            success: false,
+            ..
+        } => println!(
+            "[ {} ] Biber failed on pass {}.",
+            "FAIL".red(),
+            pass.to_string().as_str().blue()
+        ),
+        Event::MakeindexRun {
+            pass,
+            success: true,
+            ..
+        } => println!(
+            "[  {}  ] Ran makeindex on pass {}.",
+            "OK".green(),
+            pass.to_string().as_str().blue()
+        ),
+        Event::MakeindexRun {
+            pass,
+            success: false,
+            ..
+        } => println!(
+            "[ {} ] Makeindex failed on pass {}.",
+            "FAIL".red(),
+            pass.to_string().as_str().blue()
+        ),
+        Event::Diagnostic(diagnostic) => {
+            if diagnostic.kind.is_error() {
+                println!("[ {} ] {}", "FAIL".red(), diagnostic);
+            } else {
+                println!("[ {} ] {}", "WARN".yellow(), diagnostic);
+            }
+        }
+        Event::Artifact { path } => println!("[  {}  ] Wrote {}.", "OK".green(), path),
+        Event::BuildFinished {
+            success,
+            passes,
+            stable,
+            errors,
+            warnings,
+            undefined_references,
+            undefined_citations,
+            bad_boxes,
+        } => {
+            if !success {
+                println!("[ {} ] Document failed to compile.", "FAIL".red());
+            } else if !stable {
+                println!(
+                    "[ {} ] Stopped after {} passes; the document may not be stable.",
+                    "WARN".yellow(),
+                    passes.to_string().as_str().blue()
+                );
+            } else {
+                println!("[  {}  ] Document compiled successfully!", "OK".green());
+            }
+            if errors > 0 || warnings > 0 {
+                println!(
+                    "[ {} ] {} error(s), {} warning(s): {} undefined reference(s), {} undefined citation(s), {} bad box(es).",
+                    "INFO".blue(),
+                    errors,
+                    warnings,
+                    undefined_references,
+                    undefined_citations,
+                    bad_boxes
+                );
+            }
+        }
+        Event::FileCreated { path } => println!("[  {}  ] Created {}.", "OK".green(), path),
+        Event::Skipped { path } => println!("[ {} ] Skipped {}.", "WARN".yellow(), path),
+        Event::FileMoved { from, to } => {
+            println!("[  {}  ] Moved {} to {}.", "OK".green(), from, to)
+        }
+        Event::Git {
+            step,
+            success: true,
+        } => match step.as_str() {
+            "init" => println!("[  {}  ] Git repository initialized.", "OK".green()),
+            "identity" => println!("[  {}  ] Git identity set.", "OK".green()),
+            "add" => println!("[  {}  ] Git added files.", "OK".green()),
+            "commit" => println!("[  {}  ] Git repository committed.", "OK".green()),
+            other => println!("[  {}  ] Git {} succeeded.", "OK".green(), other),
+        },
+        Event::Git {
+            step,
+            success: false,
+        } => println!("[ {} ] Git {} failed.", "FAIL".red(), step),
+        Event::Incompatibility {
+            driver,
+            package,
+            message,
+            suggested_driver,
+        } => match suggested_driver {
+            Some(suggested) => println!(
+                "[ {} ] `{}` {} Use {} in place of {}.",
+                "WARN".yellow(),
+                package,
+                message,
+                suggested.as_str().blue(),
+                driver.as_str().blue()
+            ),
+            None => println!("[ {} ] `{}` {}", "WARN".yellow(), package, message),
+        },
+        Event::Info { message } => println!("[ {} ] {}", "INFO".blue(), message),
+        Event::Warning { message } => println!("[ {} ] {}", "WARN".yellow(), message),
+        Event::Error { message } => eprintln!("{}", message),
+    }
+}
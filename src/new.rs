@@ -16,187 +16,292 @@
 // | <https://github.com/ethanbarry> is the author's profile.                                                                          |
 // + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
 
-use crate::{config::*, DocumentType};
-use colored::*;
+use crate::presentation::Variant;
+use crate::{
+    compat, config::*, math, message::*, registry::*, template::*, thesis, vcs, DocumentType,
+    TexrsError,
+};
 use std::error::Error;
 use std::io;
 use std::io::Write;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::{fs, fs::File};
 
-const ARTICLE_TEMPLATE: &str = include_str!("../res/article.tex");
-const LETTER_TEMPLATE: &str = include_str!("../res/letter.tex");
-const RECIPE_TEMPLATE: &str = include_str!("../res/recipe.tex");
 const BIBTEX_TEMPLATE: &str = include_str!("../res/refs.bib");
-const NOTES_TEMPLATE: &str = include_str!("../res/notes.tex");
-const BOOK_TEMPLATE: &str = include_str!("../res/book.tex");
 
 /* -------------------------------------------------------------------- */
 /// This method creates a directory structure based on a ProjectConfig
 /// struct passed in by the caller. It returns a result depending on the
-/// file IO, which may experience an issue.
+/// file IO, which may experience an issue. Templates are rendered with
+/// placeholders filled from the config, & refused if the driver can't
+/// compile them, unless `force` is set. The project goes in the
+/// directory `root`, which mustn't exist yet. It's staged beside it,
+/// made a git repository there if its `[vcs]` table says so, & moved
+/// into place whole, so a failure, git's included, leaves nothing
+/// behind. Progress is reported as events in the given `format`.
 ///
 /// ## Usage
 ///
 /// ```rust
-/// create_directories(config).expect("File IO failed.");
+/// let root = PathBuf::from(config.get_name());
+/// create_directories(config, &root, false, MessageFormat::Human).expect("File IO failed.");
 /// ```
 /* -------------------------------------------------------------------- */
-pub fn create_directories(config: ProjectConfig) -> Result<(), Box<dyn Error>> {
-    fs::create_dir(config.get_name())?;
-    if config.get_graphics() {
-        fs::create_dir(config.get_name() + "/graphics")?;
-        println!("[  {}  ] Created graphics dir.", "OK".green());
-    } else {
-        println!("[ {} ] Skipped graphics dir.", "WARN".yellow());
+pub fn create_directories(
+    mut config: ProjectConfig,
+    root: &Path,
+    force: bool,
+    format: MessageFormat,
+) -> Result<(), Box<dyn Error>> {
+    // Render everything up front, so that a template error leaves no
+    // half-made project behind.
+    let entry = resolve(&config)?;
+    // New presentations build a handout too, & list their variants in
+    // the config so that they're easy to change.
+    if config.get_doctype() == DocumentType::Presentation {
+        let mut presentation = PresentationInfo::default();
+        presentation.set_variants(Some(vec![Variant::Slides, Variant::Handout]));
+        config.set_presentation(Some(presentation));
     }
-
-    if config.get_citations() {
-        fs::create_dir(config.get_name() + "/bib")?;
-        let mut file = File::create(config.get_name() + "/bib/refs.bib")?;
-        file.write_all(BIBTEX_TEMPLATE.as_bytes())?;
-        println!("[  {}  ] Created references dir.", "OK".green());
+    // Likewise a math article lists how its theorems are set up,
+    if config.get_doctype() == DocumentType::MathArticle {
+        let mut math = MathInfo::default();
+        math.set_numbering(Some(math.get_numbering()));
+        math.set_shared_counter(Some(math.get_shared_counter()));
+        math.set_unicode_math(Some(math.get_unicode_math()));
+        config.set_math(Some(math));
+    }
+    // & a thesis the details of its front matter.
+    if config.get_doctype() == DocumentType::Thesis {
+        let mut thesis = ThesisInfo::default();
+        thesis.set_university(Some(String::new()));
+        thesis.set_department(Some(String::new()));
+        thesis.set_degree(Some(String::new()));
+        thesis.set_advisor(Some(String::new()));
+        thesis.set_profile(Some(thesis.get_profile()));
+        config.set_thesis(Some(thesis));
+    }
+    let files = entry.render(&config)?;
+    compat::check_driver(
+        &config,
+        &rendered_packages(&files, &config.get_name()),
+        force,
+        format,
+    )?;
+    let bib_source = render(BIBTEX_TEMPLATE, &[], &context(&config))?;
+    let vcs = config.get_vcs();
+    let message = if vcs.get_enabled() {
+        Some(vcs::commit_message(&config)?)
     } else {
-        println!("[ {} ] Skipped references dir.", "WARN".yellow());
+        None
+    };
+
+    if root.exists() {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("`{}` already exists.", root.display()),
+        )));
     }
 
-    fs::create_dir(config.get_name() + "/tex")?;
+    let parent = root.parent().unwrap_or(Path::new(""));
+    if !parent.as_os_str().is_empty() && !parent.is_dir() {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("`{}` doesn't exist.", parent.display()),
+        )));
+    }
 
-    match config.get_doctype() {
-        DocumentType::Article => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(ARTICLE_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
-        }
-        DocumentType::Book => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(BOOK_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
-        }
-        DocumentType::Thesis => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(ARTICLE_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
-        }
-        DocumentType::MathArticle => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(ARTICLE_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
+    // The project is put together in a hidden directory beside where it
+    // goes, & only moved into place once every file is written & committed.
+    // If any step fails, the staging directory goes & nothing is reported
+    // as created.
+    let stage = staging_dir(root);
+    fs::create_dir(&stage)?;
+    let staged = stage_project(
+        &stage,
+        root,
+        &config,
+        entry.provides_bibliography(),
+        &files,
+        &bib_source,
+    )
+    .and_then(|mut created| {
+        if let Some(message) = &message {
+            created.extend(vcs::init_repository(&stage, &vcs, message)?);
         }
-        DocumentType::Presentation => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(ARTICLE_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
+        fs::rename(&stage, root)?;
+        Ok(created)
+    });
+    match staged {
+        Ok(created) => {
+            for event in created {
+                emit(format, event);
+            }
         }
-        DocumentType::Notes => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(NOTES_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
-        }
-        DocumentType::Letter => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(LETTER_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
-        }
-        DocumentType::Recipe => {
-            let mut file =
-                File::create(config.get_name() + "/tex/" + config.get_name().as_str() + ".tex")?;
-            file.write_all(RECIPE_TEMPLATE.as_bytes())?;
-            println!("[  {}  ] Created tex dir.", "OK".green());
+        Err(err) => {
+            let _ = fs::remove_dir_all(&stage);
+            return Err(err);
         }
     }
 
-    match write_project_config(&config) {
-        Ok(_) => println!("[  {}  ] Project config written.", "OK".green()),
-        Err(err) => {
-            println!("[ {} ]", "FAIL".red());
-            eprintln!("{}", err);
+    Ok(())
+}
+
+/* -------------------------------------------------------------------- */
+/// Turns a project name into a jobname that's safe as a file name & as a
+/// TeX `\jobname`: ASCII letters, digits, `-` & `_`, with each run of
+/// anything else made a single `-`. A name with no letter or digit in
+/// it is a `TexrsError::InvalidChoice`.
+///
+/// ## Usage
+///
+/// ```rust
+/// assert_eq!(jobname("My Paper (draft)")?, "My-Paper-draft");
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn jobname(name: &str) -> Result<String, TexrsError> {
+    let mut jobname = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            jobname.push(c);
+        } else if !jobname.ends_with('-') {
+            jobname.push('-');
         }
     }
+    let jobname = jobname.trim_matches('-');
+    if jobname.chars().any(|c| c.is_ascii_alphanumeric()) {
+        Ok(jobname.to_owned())
+    } else {
+        Err(TexrsError::InvalidChoice(name.to_owned()))
+    }
+}
+
+/// Where a project going to `root` is staged: a hidden directory beside
+/// it, named for the process so that two runs can't collide.
+fn staging_dir(root: &Path) -> PathBuf {
+    let name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".to_owned());
+    let parent = root.parent().unwrap_or(Path::new(""));
+    parent.join(format!(".{}.texrs-{}", name, process::id()))
+}
+
+/// Writes every directory & file of the project into `stage`. Returns
+/// the events to report once it's moved to `root`, with their paths
+/// under `root`.
+fn stage_project(
+    stage: &Path,
+    root: &Path,
+    config: &ProjectConfig,
+    provides_bibliography: bool,
+    files: &[TemplateFile],
+    bib_source: &str,
+) -> Result<Vec<Event>, Box<dyn Error>> {
+    let created = |path: &Path| Event::FileCreated {
+        path: root.join(path).display().to_string(),
+    };
+    let skipped = |path: &Path| Event::Skipped {
+        path: root.join(path).display().to_string(),
+    };
+    let mut events = vec![Event::FileCreated {
+        path: root.display().to_string(),
+    }];
+
+    let graphics_dir = Path::new("graphics");
+    if config.get_graphics() {
+        fs::create_dir(stage.join(graphics_dir))?;
+        events.push(created(graphics_dir));
+    } else {
+        events.push(skipped(graphics_dir));
+    }
 
-    match git_init(config.clone()) {
-        Ok(_) => {}
-        Err(err) => {
-            println!("[ {} ] Git init failed.", "FAIL".red());
-            eprintln!("{}", err);
+    let bib_dir = Path::new("bib");
+    if config.get_citations() {
+        fs::create_dir(stage.join(bib_dir))?;
+        events.push(created(bib_dir));
+        // A template that brings its own bibliography replaces the default.
+        if !provides_bibliography {
+            let bib_file = bib_dir.join(BIBLIOGRAPHY_FILE);
+            let mut file = File::create(stage.join(&bib_file))?;
+            file.write_all(bib_source.as_bytes())?;
+            events.push(created(&bib_file));
         }
+    } else {
+        events.push(skipped(bib_dir));
     }
-    match git_add(config.clone()) {
-        Ok(_) => {}
-        Err(err) => {
-            println!("[ {} ] Git add failed.", "FAIL".red());
-            eprintln!("{}", err);
+
+    let tex_dir = Path::new("tex");
+    fs::create_dir(stage.join(tex_dir))?;
+    events.push(created(tex_dir));
+
+    for file in files {
+        let path = stage.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+        fs::write(&path, &file.contents)?;
+        events.push(created(&file.path));
     }
-    match git_commit(config) {
-        Ok(_) => {}
-        Err(err) => {
-            println!("[ {} ] Git commit failed.", "FAIL".red());
-            eprintln!("{}", err);
+
+    let tex = stage.join(tex_dir);
+    let generated = match config.get_doctype() {
+        DocumentType::Thesis => Some(thesis::write_metadata(config, &tex)?),
+        DocumentType::MathArticle => Some(math::write_setup(config, &tex)?),
+        _ => None,
+    };
+    if let Some((path, _)) = generated {
+        events.push(created(path.strip_prefix(stage).unwrap_or(&path)));
+    }
+
+    let vcs = config.get_vcs();
+    if vcs.get_enabled() {
+        for (name, contents) in vcs::files(&vcs) {
+            fs::write(stage.join(name), contents)?;
+            events.push(created(Path::new(name)));
         }
     }
 
-    Ok(())
+    write_project_config_at(stage, config)?;
+    events.push(created(Path::new(CONFIG_FILE)));
+
+    Ok(events)
 }
 
-fn git_init(config: ProjectConfig) -> Result<(), Box<dyn Error>> {
-    let mut git_init = Command::new("git");
-    let output = git_init
-        .args(["init"])
-        .current_dir(config.get_name())
-        .output()?;
-    if output.status.success() {
-        println!("[  {}  ] Git repository initialized.", "OK".green());
-        Ok(())
-    } else {
-        Err(Box::new(io::Error::new(
-            io::ErrorKind::Other,
-            "git failed.",
-        )))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_names_are_kept() {
+        assert_eq!(jobname("paper").unwrap(), "paper");
+        assert_eq!(jobname("my_thesis-2024").unwrap(), "my_thesis-2024");
     }
-}
 
-fn git_add(config: ProjectConfig) -> Result<(), Box<dyn Error>> {
-    let mut git_add = Command::new("git");
-    if git_add
-        .args(["add", "."])
-        .current_dir(config.get_name())
-        .output()?
-        .status
-        .success()
-    {
-        println!("[  {}  ] Git added files.", "OK".green());
-        Ok(())
-    } else {
-        Err(Box::new(io::Error::new(
-            io::ErrorKind::Other,
-            "git failed.",
-        )))
+    #[test]
+    fn unsafe_runs_become_one_dash() {
+        assert_eq!(jobname("My Paper (draft)").unwrap(), "My-Paper-draft");
+        assert_eq!(jobname("a  &  b").unwrap(), "a-b");
+        assert_eq!(jobname("notes/week 1.tex").unwrap(), "notes-week-1-tex");
+        assert_eq!(jobname("Résumé").unwrap(), "R-sum");
     }
-}
 
-fn git_commit(config: ProjectConfig) -> Result<(), Box<dyn Error>> {
-    let mut git_commit = Command::new("git");
-    if git_commit
-        .args(["commit", "-m", "\"Initialize repository.\""])
-        .current_dir(config.get_name())
-        .output()?
-        .status
-        .success()
-    {
-        println!("[  {}  ] Git repository committed.", "OK".green());
-        Ok(())
-    } else {
-        Err(Box::new(io::Error::new(
-            io::ErrorKind::Other,
-            "git failed.",
-        )))
+    #[test]
+    fn edges_are_trimmed() {
+        assert_eq!(jobname("  paper  ").unwrap(), "paper");
+        assert_eq!(jobname("--paper--").unwrap(), "paper");
+        assert_eq!(jobname("(draft)").unwrap(), "draft");
+    }
+
+    #[test]
+    fn names_without_letters_or_digits_are_refused() {
+        for name in ["", "   ", "---", "()!", "_-_"] {
+            assert!(
+                matches!(jobname(name), Err(TexrsError::InvalidChoice(n)) if n == name),
+                "{:?} was accepted",
+                name
+            );
+        }
     }
 }
@@ -0,0 +1,59 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use clap::ValueEnum;
+use serde_derive::{Deserialize, Serialize};
+
+/* -------------------------------------------------------------------- */
+/// One PDF built from a Beamer presentation. The slides are the document
+/// as written; the others are built from a small wrapper in `target/`
+/// that sets them up & then `\input`s the document, so the source never
+/// changes between variants.
+/* -------------------------------------------------------------------- */
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    /// The slides, with every overlay.
+    Slides,
+    /// One page per frame, built with the `handout` class option.
+    Handout,
+    /// Slides with the speaker's notes on a second screen.
+    Notes,
+}
+
+impl Variant {
+    /// The jobname of the variant of the document called `name`, which
+    /// names its PDF: the slides keep the name, the rest add a suffix.
+    pub fn jobname(&self, name: &str) -> String {
+        match self {
+            Variant::Slides => name.to_owned(),
+            Variant::Handout => format!("{}-handout", name),
+            Variant::Notes => format!("{}-notes", name),
+        }
+    }
+
+    /// What the wrapper sets up before the document, or `None` for the
+    /// slides, which need no wrapper. The template's preamble loads
+    /// `pgfpages` when it finds `\TexrsNotes` defined.
+    pub fn setup(&self) -> Option<&'static str> {
+        match self {
+            Variant::Slides => None,
+            Variant::Handout => Some("\\PassOptionsToClass{handout}{beamer}"),
+            Variant::Notes => Some("\\def\\TexrsNotes{}"),
+        }
+    }
+}
@@ -0,0 +1,760 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::compat::{self, Severity};
+use crate::{config::*, template::*, DocumentType, TexrsError};
+use clap::ValueEnum;
+use colored::*;
+use serde_derive::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The manifest at the top of every user template directory.
+pub const MANIFEST_FILE: &str = "template.toml";
+
+/// Where a project keeps templates of its own, relative to the CWD.
+pub const LOCAL_TEMPLATE_DIR: &str = ".texrs/templates";
+
+/// Files with these extensions are rendered; anything else, such as a
+/// logo, is copied byte for byte.
+const RENDERED_EXTENSIONS: [&str; 8] = ["tex", "bib", "sty", "cls", "bbx", "cbx", "txt", "md"];
+
+/// The built-in templates, one per `DocumentType`, with the defaults a
+/// project made from them starts with.
+struct Builtin {
+    doctype: DocumentType,
+    description: &'static str,
+    driver: &'static str,
+    citations: bool,
+    graphics: bool,
+    /// Each file's path in the project & its source; the first is the
+    /// main file.
+    files: &'static [(&'static str, &'static str)],
+    placeholders: &'static [&'static str],
+}
+
+const ARTICLE_FILES: &[(&str, &str)] = &[("tex/main.tex", include_str!("../res/article.tex"))];
+const ARTICLE_PLACEHOLDERS: &[&str] = &[
+    "title",
+    "author",
+    "institution",
+    "course",
+    "date",
+    "paper",
+    "font",
+    "bibliography",
+];
+
+const BUILTINS: [Builtin; 8] = [
+    Builtin {
+        doctype: DocumentType::Article,
+        description: "A short paper with a bibliography.",
+        driver: "pdflatex",
+        citations: true,
+        graphics: true,
+        files: ARTICLE_FILES,
+        placeholders: ARTICLE_PLACEHOLDERS,
+    },
+    Builtin {
+        doctype: DocumentType::Book,
+        description: "A book, divided into chapters.",
+        driver: "xelatex",
+        citations: true,
+        graphics: true,
+        files: &[
+            ("tex/main.tex", include_str!("../res/book/main.tex")),
+            (
+                "tex/chapters/01-introduction.tex",
+                include_str!("../res/book/chapters/01-introduction.tex"),
+            ),
+            (
+                "tex/chapters/02-first-chapter.tex",
+                include_str!("../res/book/chapters/02-first-chapter.tex"),
+            ),
+        ],
+        placeholders: &["title", "author", "language"],
+    },
+    Builtin {
+        doctype: DocumentType::Thesis,
+        description: "A thesis, with front matter filled in from [thesis].",
+        driver: "xelatex",
+        citations: true,
+        graphics: true,
+        files: &[
+            ("tex/main.tex", include_str!("../res/thesis/main.tex")),
+            (
+                "tex/frontmatter/titlepage.tex",
+                include_str!("../res/thesis/frontmatter/titlepage.tex"),
+            ),
+            (
+                "tex/frontmatter/declaration.tex",
+                include_str!("../res/thesis/frontmatter/declaration.tex"),
+            ),
+            (
+                "tex/frontmatter/abstract.tex",
+                include_str!("../res/thesis/frontmatter/abstract.tex"),
+            ),
+            (
+                "tex/frontmatter/acknowledgments.tex",
+                include_str!("../res/thesis/frontmatter/acknowledgments.tex"),
+            ),
+            (
+                "tex/frontmatter/abbreviations.tex",
+                include_str!("../res/thesis/frontmatter/abbreviations.tex"),
+            ),
+            (
+                "tex/chapters/01-introduction.tex",
+                include_str!("../res/thesis/chapters/01-introduction.tex"),
+            ),
+            (
+                "tex/chapters/02-background.tex",
+                include_str!("../res/thesis/chapters/02-background.tex"),
+            ),
+            (
+                "tex/chapters/03-conclusion.tex",
+                include_str!("../res/thesis/chapters/03-conclusion.tex"),
+            ),
+        ],
+        placeholders: &["bibliography"],
+    },
+    Builtin {
+        doctype: DocumentType::Presentation,
+        description: "Beamer slides for a talk, with a handout & notes.",
+        driver: "xelatex",
+        citations: true,
+        graphics: true,
+        files: &[("tex/main.tex", include_str!("../res/presentation.tex"))],
+        placeholders: &["title", "author", "date", "bibliography"],
+    },
+    Builtin {
+        doctype: DocumentType::MathArticle,
+        description: "An amsart paper, with theorems set up from [math].",
+        driver: "xelatex",
+        citations: true,
+        graphics: true,
+        files: &[("tex/main.tex", include_str!("../res/math-article.tex"))],
+        placeholders: &["title", "author", "date", "paper", "bibliography"],
+    },
+    Builtin {
+        doctype: DocumentType::Notes,
+        description: "Lecture or study notes.",
+        driver: "pdflatex",
+        citations: false,
+        graphics: false,
+        files: &[("tex/main.tex", include_str!("../res/notes.tex"))],
+        placeholders: &["title", "author", "date", "paper", "language", "font"],
+    },
+    Builtin {
+        doctype: DocumentType::Letter,
+        description: "A formal letter.",
+        driver: "xelatex",
+        citations: false,
+        graphics: true,
+        files: &[("tex/main.tex", include_str!("../res/letter.tex"))],
+        placeholders: &["title", "author", "email", "date", "paper", "font"],
+    },
+    Builtin {
+        doctype: DocumentType::Recipe,
+        description: "A recipe card.",
+        driver: "xelatex",
+        citations: false,
+        graphics: true,
+        files: &[("tex/main.tex", include_str!("../res/recipe.tex"))],
+        placeholders: &["title", "language", "font"],
+    },
+];
+
+/// Where a template was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateOrigin {
+    /// Compiled into texrs.
+    Builtin,
+    /// A template directory on disk.
+    Directory(PathBuf),
+}
+
+/// One file of a template. `path` is relative to the project root.
+#[derive(Debug, Clone)]
+pub struct TemplateFile {
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+}
+
+/* -------------------------------------------------------------------- */
+/// A template a project can be made from: the files it creates, the
+/// placeholders they use, & the defaults it gives the project. The
+/// `main` file is the document itself; it's written to `tex/NAME.tex`,
+/// & every other file keeps its path relative to the project root.
+/* -------------------------------------------------------------------- */
+#[derive(Debug, Clone)]
+pub struct TemplateEntry {
+    pub name: String,
+    pub description: String,
+    pub doctype: DocumentType,
+    pub driver: String,
+    pub citations: bool,
+    pub graphics: bool,
+    pub placeholders: Vec<String>,
+    pub main: PathBuf,
+    pub files: Vec<TemplateFile>,
+    pub origin: TemplateOrigin,
+}
+
+impl TemplateEntry {
+    /// Gives `config` this template's defaults, & records the template
+    /// in it if it isn't a built-in one.
+    pub fn configure(&self, config: &mut ProjectConfig) {
+        config.set_driver(&self.driver);
+        config.set_citations(self.citations);
+        config.set_graphics(self.graphics);
+        config.set_doctype(self.doctype);
+        self.select(config);
+    }
+
+    /// Records the template in `config` without touching its defaults.
+    pub fn select(&self, config: &mut ProjectConfig) {
+        config.set_doctype(self.doctype);
+        match self.origin {
+            TemplateOrigin::Builtin => config.set_template(None),
+            TemplateOrigin::Directory(_) => config.set_template(Some(self.name.clone())),
+        }
+    }
+
+    /// Whether the template ships its own bibliography, in place of the
+    /// default `bib/refs.bib`.
+    pub fn provides_bibliography(&self) -> bool {
+        self.files.iter().any(|file| file.path.starts_with("bib"))
+    }
+
+    /// Where the main file of a template lands in a project called `name`.
+    pub fn main_path(name: &str) -> PathBuf {
+        Path::new("tex").join(format!("{}.tex", name))
+    }
+
+    /* ---------------------------------------------------------------- */
+    /// Renders every file of the template for the project `config`. The
+    /// paths of the returned files are relative to the project root, with
+    /// the main file moved to `tex/NAME.tex`.
+    ///
+    /// ## Usage
+    ///
+    /// ```rust
+    /// for file in entry.render(&config)? {
+    ///     fs::write(root.join(&file.path), &file.contents)?;
+    /// }
+    /// ```
+    /* ---------------------------------------------------------------- */
+    pub fn render(&self, config: &ProjectConfig) -> Result<Vec<TemplateFile>, TexrsError> {
+        let context = context(config);
+        let declared: Vec<&str> = self.placeholders.iter().map(String::as_str).collect();
+        let mut rendered = Vec::with_capacity(self.files.len());
+        for file in &self.files {
+            let path = if file.path == self.main {
+                TemplateEntry::main_path(&config.get_name())
+            } else {
+                file.path.clone()
+            };
+            let contents = if is_rendered(&file.path) {
+                let source = std::str::from_utf8(&file.contents).map_err(|_| {
+                    TexrsError::TemplateError(format!(
+                        "`{}` isn't valid UTF-8.",
+                        file.path.display()
+                    ))
+                })?;
+                render(source, &declared, &context)
+                    .map_err(|err| in_file(&file.path, err))?
+                    .into_bytes()
+            } else {
+                file.contents.clone()
+            };
+            rendered.push(TemplateFile { path, contents });
+        }
+        Ok(rendered)
+    }
+}
+
+/// Prefixes a template error with the file it came from.
+fn in_file(path: &Path, err: TexrsError) -> TexrsError {
+    match err {
+        TexrsError::TemplateError(msg) => {
+            TexrsError::TemplateError(format!("{}: {}", path.display(), msg))
+        }
+        err => err,
+    }
+}
+
+fn is_rendered(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| RENDERED_EXTENSIONS.contains(&ext))
+}
+
+/// The template a project is made from: its user template, if it names
+/// one, & otherwise the built-in one for its doctype.
+pub fn resolve(config: &ProjectConfig) -> Result<TemplateEntry, TexrsError> {
+    match config.get_template() {
+        Some(name) => Registry::discover().find(&name).cloned(),
+        None => Ok(builtin(config.get_doctype())),
+    }
+}
+
+/// The packages a template's rendered `files` load, for a project
+/// called `name`.
+pub fn rendered_packages(files: &[TemplateFile], name: &str) -> Vec<String> {
+    compat::files_packages(
+        &TemplateEntry::main_path(name),
+        files.iter().filter_map(|file| {
+            Some((
+                file.path.as_path(),
+                std::str::from_utf8(&file.contents).ok()?,
+            ))
+        }),
+    )
+}
+
+/// The name a built-in template goes by, e.g. `math-article`.
+pub fn builtin_name(doctype: DocumentType) -> String {
+    doctype
+        .to_possible_value()
+        .map(|value| value.get_name().to_owned())
+        .unwrap_or_default()
+}
+
+/// The built-in template for `doctype`, with its defaults overridden by
+/// those the user config sets for the doctype.
+pub fn builtin(doctype: DocumentType) -> TemplateEntry {
+    let builtin = BUILTINS
+        .iter()
+        .find(|builtin| builtin.doctype == doctype)
+        .unwrap_or(&BUILTINS[0]);
+    let defaults = user_config().get_doctype(builtin.doctype);
+    let main = PathBuf::from(builtin.files[0].0);
+    TemplateEntry {
+        name: builtin_name(builtin.doctype),
+        description: builtin.description.to_owned(),
+        doctype: builtin.doctype,
+        driver: defaults
+            .get_driver()
+            .unwrap_or_else(|| builtin.driver.to_owned()),
+        citations: defaults.get_citations().unwrap_or(builtin.citations),
+        graphics: defaults.get_graphics().unwrap_or(builtin.graphics),
+        placeholders: builtin.placeholders.iter().map(|s| s.to_string()).collect(),
+        main,
+        files: builtin
+            .files
+            .iter()
+            .map(|(path, source)| TemplateFile {
+                path: PathBuf::from(path),
+                contents: source.as_bytes().to_vec(),
+            })
+            .collect(),
+        origin: TemplateOrigin::Builtin,
+    }
+}
+
+/// The `template.toml` manifest. Anything left out falls back to the
+/// built-in template for `doctype`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    name: Option<String>,
+    description: Option<String>,
+    doctype: Option<DocumentType>,
+    driver: Option<String>,
+    citations: Option<bool>,
+    graphics: Option<bool>,
+    main: Option<PathBuf>,
+    placeholders: Option<Vec<String>>,
+}
+
+/* -------------------------------------------------------------------- */
+/// Loads the template in `dir`: its `template.toml` manifest & every
+/// other file under it, at any depth.
+///
+/// ## Manifest
+/// name: the template's name; defaults to the directory's name,
+/// description: a one-line description,
+/// doctype: the `DocumentType` of projects made from it,
+/// driver, citations, graphics: the project's defaults,
+/// main: the document itself; defaults to `tex/main.tex`,
+/// placeholders: the placeholders its files may use
+/* -------------------------------------------------------------------- */
+pub fn load_template(dir: &Path) -> Result<TemplateEntry, TexrsError> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let text = fs::read_to_string(&manifest_path).map_err(|err| {
+        TexrsError::TemplateError(format!("`{}`: {}", manifest_path.display(), err))
+    })?;
+    let manifest: Manifest = toml::from_str(&text).map_err(|err| {
+        TexrsError::TemplateError(format!("`{}`: {}", manifest_path.display(), err.message()))
+    })?;
+
+    let mut files = Vec::new();
+    collect_files(dir, Path::new(""), &mut files).map_err(TexrsError::IoError)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let main = manifest
+        .main
+        .unwrap_or_else(|| PathBuf::from("tex/main.tex"));
+    if !files.iter().any(|file| file.path == main) {
+        return Err(TexrsError::TemplateError(format!(
+            "`{}` has no main file `{}`.",
+            dir.display(),
+            main.display()
+        )));
+    }
+
+    let defaults = builtin(manifest.doctype.unwrap_or(DocumentType::Article));
+    let name = manifest.name.unwrap_or_else(|| {
+        dir.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    Ok(TemplateEntry {
+        name,
+        description: manifest.description.unwrap_or_default(),
+        doctype: defaults.doctype,
+        driver: manifest.driver.unwrap_or(defaults.driver),
+        citations: manifest.citations.unwrap_or(defaults.citations),
+        graphics: manifest.graphics.unwrap_or(defaults.graphics),
+        placeholders: manifest.placeholders.unwrap_or_default(),
+        main,
+        files,
+        origin: TemplateOrigin::Directory(dir.to_path_buf()),
+    })
+}
+
+/// Reads every file under `dir` into `files`, skipping the manifest &
+/// any `.git` directory.
+fn collect_files(
+    dir: &Path,
+    relative: &Path,
+    files: &mut Vec<TemplateFile>,
+) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        if path == Path::new(MANIFEST_FILE) || entry.file_name() == ".git" {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &path, files)?;
+        } else {
+            files.push(TemplateFile {
+                path,
+                contents: fs::read(entry.path())?,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The directories user templates are looked for in, from the lowest
+/// precedence to the highest: the user's config dir, then the project's.
+pub fn template_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(config_dir) = user_config_dir() {
+        dirs.push(config_dir.join("templates"));
+    }
+    dirs.push(PathBuf::from(LOCAL_TEMPLATE_DIR));
+    dirs
+}
+
+/// A template directory that couldn't be loaded.
+#[derive(Debug, Clone)]
+pub struct BrokenTemplate {
+    pub name: String,
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/* -------------------------------------------------------------------- */
+/// Every template texrs knows of: the built-in ones, then those in each
+/// of `template_dirs`. A template replaces any earlier one of the same
+/// name, so a house-style `article` takes the built-in one's place. A
+/// broken one does too, so that its error isn't hidden behind the
+/// template it was meant to replace.
+///
+/// ## Usage
+///
+/// ```rust
+/// let registry = Registry::discover();
+/// let entry = registry.find("article")?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub struct Registry {
+    templates: Vec<TemplateEntry>,
+    broken: Vec<BrokenTemplate>,
+}
+
+impl Registry {
+    pub fn discover() -> Registry {
+        let mut registry = Registry {
+            templates: BUILTINS.iter().map(|b| builtin(b.doctype)).collect(),
+            broken: Vec::new(),
+        };
+        for dir in template_dirs() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect();
+            paths.sort();
+            for path in paths {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                match load_template(&path) {
+                    Ok(entry) => {
+                        registry.broken.retain(|broken| broken.name != entry.name);
+                        registry.insert(entry);
+                    }
+                    Err(err) => {
+                        registry.templates.retain(|entry| entry.name != name);
+                        registry.broken.retain(|broken| broken.name != name);
+                        registry.broken.push(BrokenTemplate {
+                            name,
+                            path,
+                            error: match err {
+                                TexrsError::TemplateError(msg) => msg,
+                                err => err.to_string(),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        registry
+    }
+
+    fn insert(&mut self, entry: TemplateEntry) {
+        match self.templates.iter_mut().find(|t| t.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.templates.push(entry),
+        }
+    }
+
+    pub fn templates(&self) -> &[TemplateEntry] {
+        &self.templates
+    }
+
+    /// The user templates, i.e. those that aren't built in.
+    pub fn user_templates(&self) -> impl Iterator<Item = &TemplateEntry> {
+        self.templates
+            .iter()
+            .filter(|t| t.origin != TemplateOrigin::Builtin)
+    }
+
+    pub fn broken(&self) -> &[BrokenTemplate] {
+        &self.broken
+    }
+
+    /// The template called `name`.
+    pub fn find(&self, name: &str) -> Result<&TemplateEntry, TexrsError> {
+        if let Some(entry) = self.templates.iter().find(|t| t.name == name) {
+            return Ok(entry);
+        }
+        match self.broken.iter().find(|b| b.name == name) {
+            Some(broken) => Err(TexrsError::TemplateError(broken.error.clone())),
+            None => Err(TexrsError::TemplateError(format!(
+                "no template named `{}`.",
+                name
+            ))),
+        }
+    }
+}
+
+/// A project config for trying a template out, as `texrs new` would make
+/// it for a project called `NAME`.
+fn sample_config(entry: &TemplateEntry) -> ProjectConfig {
+    let mut config = ProjectConfig::new();
+    config.set_name("NAME");
+    entry.configure(&mut config);
+    config
+}
+
+/// Prints every template, with its default driver, what it needs, & its
+/// description, followed by any template directory that's broken.
+pub fn list_templates(registry: &Registry) {
+    println!(
+        "{:<16} {:<10} {:<9} {:<8} DESCRIPTION",
+        "NAME", "DRIVER", "CITATIONS", "GRAPHICS"
+    );
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    for entry in registry.templates() {
+        let mut description = entry.description.clone();
+        if let TemplateOrigin::Directory(path) = &entry.origin {
+            description = format!("{} ({})", description, path.display())
+                .trim_start()
+                .to_owned();
+        }
+        println!(
+            "{:<16} {:<10} {:<9} {:<8} {}",
+            entry.name,
+            entry.driver,
+            yes_no(entry.citations),
+            yes_no(entry.graphics),
+            description
+        );
+    }
+    for broken in registry.broken() {
+        println!("[ {} ] {}", "WARN".yellow(), broken.error);
+    }
+}
+
+/* -------------------------------------------------------------------- */
+/// Prints the files & directories a template creates, for a project
+/// called `NAME`.
+///
+/// ## Usage
+///
+/// ```rust
+/// show_template(Registry::discover().find("article")?)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn show_template(entry: &TemplateEntry) -> Result<(), Box<dyn Error>> {
+    let config = sample_config(entry);
+    let origin = match &entry.origin {
+        TemplateOrigin::Builtin => "built in".to_owned(),
+        TemplateOrigin::Directory(path) => path.display().to_string(),
+    };
+    println!("{}: {} ({})", entry.name.bold(), entry.description, origin);
+    println!(
+        "Driver {}, citations {}, graphics {}.",
+        entry.driver,
+        if entry.citations { "on" } else { "off" },
+        if entry.graphics { "on" } else { "off" }
+    );
+
+    let mut paths = vec![PathBuf::from(CONFIG_FILE), PathBuf::from("tex/")];
+    if entry.graphics {
+        paths.push(PathBuf::from("graphics/"));
+    }
+    if entry.citations {
+        paths.push(PathBuf::from("bib/"));
+        if !entry.provides_bibliography() {
+            paths.push(Path::new("bib").join(BIBLIOGRAPHY_FILE));
+        }
+    }
+    paths.extend(entry.render(&config)?.into_iter().map(|file| file.path));
+    paths.sort();
+    for path in paths {
+        println!("  NAME/{}", path.display());
+    }
+    Ok(())
+}
+
+/* -------------------------------------------------------------------- */
+/// Checks that a template renders, that every placeholder it declares
+/// is used, & that its driver can compile its preamble. Prints each
+/// result, & fails with a `TexrsError::TemplateError` if any check did.
+/* -------------------------------------------------------------------- */
+pub fn check_template(entry: &TemplateEntry) -> Result<(), Box<dyn Error>> {
+    let config = sample_config(entry);
+    let files = match entry.render(&config) {
+        Ok(files) => {
+            println!("[  {}  ] `{}` renders.", "OK".green(), entry.name);
+            files
+        }
+        Err(err) => {
+            println!("[ {} ] {}", "FAIL".red(), err);
+            return Err(Box::new(TexrsError::TemplateError(format!(
+                "`{}` doesn't render.",
+                entry.name
+            ))));
+        }
+    };
+
+    let sources: Vec<(&Path, &str)> = entry
+        .files
+        .iter()
+        .filter_map(|file| {
+            Some((
+                file.path.as_path(),
+                std::str::from_utf8(&file.contents).ok()?,
+            ))
+        })
+        .collect();
+    let unused: Vec<&String> = entry
+        .placeholders
+        .iter()
+        .filter(|name| {
+            let placeholder = format!("<<{}>>", name);
+            !sources
+                .iter()
+                .any(|(_, source)| source.contains(&placeholder))
+        })
+        .collect();
+    if unused.is_empty() {
+        println!("[  {}  ] Every placeholder resolves.", "OK".green());
+    }
+    for name in unused {
+        println!(
+            "[ {} ] `<<{}>>` is declared but never used.",
+            "WARN".yellow(),
+            name
+        );
+    }
+
+    let main = TemplateEntry::main_path(&config.get_name());
+    let packages = compat::files_packages(
+        &main,
+        files.iter().filter_map(|file| {
+            Some((
+                file.path.as_path(),
+                std::str::from_utf8(&file.contents).ok()?,
+            ))
+        }),
+    );
+    let issues = compat::check(compat::engine(&config), &packages);
+    let mut failed = false;
+    for issue in &issues {
+        match issue.severity {
+            Severity::Error => {
+                failed = true;
+                println!("[ {} ] {}", "FAIL".red(), issue);
+            }
+            Severity::Warning => println!("[ {} ] {}", "WARN".yellow(), issue),
+        }
+    }
+    if failed {
+        if let Some(driver) = compat::suggested_driver(&packages) {
+            println!(
+                "[ {} ] The preamble needs `{}`, not `{}`.",
+                "INFO".blue(),
+                driver,
+                entry.driver
+            );
+        }
+        return Err(Box::new(TexrsError::TemplateError(format!(
+            "`{}` can't be compiled with `{}`.",
+            entry.name, entry.driver
+        ))));
+    }
+    if issues.is_empty() {
+        println!(
+            "[  {}  ] `{}` suits the preamble.",
+            "OK".green(),
+            entry.driver
+        );
+    }
+    Ok(())
+}
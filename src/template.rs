@@ -0,0 +1,141 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::config::*;
+use crate::TexrsError;
+
+use std::collections::BTreeMap;
+
+/// The bibliography file every project with citations starts with.
+pub const BIBLIOGRAPHY_FILE: &str = "refs.bib";
+
+/// Values for a template's placeholders, keyed by placeholder name.
+pub type Context = BTreeMap<String, String>;
+
+/* -------------------------------------------------------------------- */
+/// Builds the placeholder values for a project. Free text such as the
+/// title & author is escaped for LaTeX; the rest is used as written.
+///
+/// ## Placeholders
+/// name: the project name,
+/// title: `[document] title`, or the project name,
+/// author: `[document] author`,
+/// date: `[document] date`, or `\today`,
+/// paper: `[document] paper`, e.g. `letterpaper` or `a4paper`,
+/// font: `[document] font`, a font package such as `ebgaramond`,
+/// bibliography: the bibliography file in `bib/`
+/* -------------------------------------------------------------------- */
+pub fn context(config: &ProjectConfig) -> Context {
+    let document = config.get_document();
+    let mut context = Context::new();
+    context.insert("name".to_owned(), config.get_name());
+    context.insert(
+        "title".to_owned(),
+        escape_tex(&document.get_title().unwrap_or_else(|| config.get_name())),
+    );
+    context.insert("author".to_owned(), escape_tex(&document.get_author()));
+    context.insert("email".to_owned(), escape_tex(&document.get_email()));
+    context.insert(
+        "institution".to_owned(),
+        escape_tex(&document.get_institution()),
+    );
+    context.insert("course".to_owned(), escape_tex(&document.get_course()));
+    context.insert("date".to_owned(), document.get_date());
+    context.insert("paper".to_owned(), document.get_paper());
+    context.insert("language".to_owned(), document.get_language());
+    context.insert("font".to_owned(), document.get_font());
+    context.insert("bibliography".to_owned(), BIBLIOGRAPHY_FILE.to_owned());
+    context
+}
+
+/* -------------------------------------------------------------------- */
+/// Replaces every `<<name>>` in `source` with its value from `context`.
+/// A placeholder that isn't in `declared`, or a declared one that has no
+/// value in `context`, is a `TexrsError::TemplateError`.
+///
+/// ## Usage
+///
+/// ```rust
+/// let tex = render("\\title{<<title>>}", &["title"], &context(&config))?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn render(source: &str, declared: &[&str], context: &Context) -> Result<String, TexrsError> {
+    if let Some(unknown) = declared.iter().find(|name| !context.contains_key(**name)) {
+        return Err(TexrsError::TemplateError(format!(
+            "the template declares `<<{}>>`, which texrs doesn't know how to fill.",
+            unknown
+        )));
+    }
+
+    let mut rendered = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("<<") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match placeholder_name(after) {
+            Some(name) => {
+                if !declared.contains(&name) {
+                    let offset = source.len() - rest.len() + start;
+                    let line = source[..offset].matches('\n').count() + 1;
+                    return Err(TexrsError::TemplateError(format!(
+                        "unknown placeholder `<<{}>>` on line {}.",
+                        name, line
+                    )));
+                }
+                rendered.push_str(&context[name]);
+                rest = &after[name.len() + 2..];
+            }
+            None => {
+                // Not a placeholder, just a pair of angle brackets.
+                rendered.push_str("<<");
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// If `text` starts with `name>>`, where `name` is lowercase letters &
+/// underscores, returns `name`.
+fn placeholder_name(text: &str) -> Option<&str> {
+    let end = text.find(">>")?;
+    let name = &text[..end];
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Escapes the characters LaTeX treats specially in running text.
+pub fn escape_tex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,156 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::build::write_if_changed;
+use crate::config::*;
+use crate::template::escape_tex;
+use crate::TexrsError;
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Where the generated metadata goes, relative to `tex/`.
+pub const METADATA_FILE: &str = "frontmatter/metadata.tex";
+
+/* -------------------------------------------------------------------- */
+/// The house rules of a university: the layout of the page & the wording
+/// of the title page & declaration. `paper` is `None` where the profile
+/// leaves the paper size to `[document] paper`.
+/* -------------------------------------------------------------------- */
+pub struct Profile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub paper: Option<&'static str>,
+    pub margins: &'static str,
+    pub spacing: &'static str,
+    pub degree_statement: &'static str,
+    pub declaration: &'static str,
+}
+
+pub const PROFILES: [Profile; 3] = [
+    Profile {
+        name: "generic",
+        description: "One-inch margins, one-and-a-half spacing.",
+        paper: None,
+        margins: "margin=1in",
+        spacing: "1.5",
+        degree_statement: "A thesis submitted for the degree of",
+        declaration: "I declare that this thesis is my own work, \\& that it has not \
+                      been submitted for a degree at any other institution.",
+    },
+    Profile {
+        name: "us",
+        description: "US letter, a wide binding margin, double spacing.",
+        paper: Some("letterpaper"),
+        margins: "left=1.5in, right=1in, top=1in, bottom=1in",
+        spacing: "2",
+        degree_statement: "A thesis submitted in partial fulfillment of the \
+                           requirements for the degree of",
+        declaration: "I certify that this thesis is my own work, \\& that all \
+                      sources have been acknowledged.",
+    },
+    Profile {
+        name: "uk",
+        description: "A4, a wide binding margin, one-and-a-half spacing.",
+        paper: Some("a4paper"),
+        margins: "left=40mm, right=25mm, top=25mm, bottom=25mm",
+        spacing: "1.5",
+        degree_statement: "A thesis submitted for the degree of",
+        declaration: "I declare that this thesis was composed by myself, that \
+                      the work contained herein is my own except where \
+                      explicitly stated otherwise in the text, \\& that it has \
+                      not been submitted for any other degree or professional \
+                      qualification.",
+    },
+];
+
+/// The profile called `name`.
+pub fn profile(name: &str) -> Result<&'static Profile, TexrsError> {
+    PROFILES
+        .iter()
+        .find(|profile| profile.name == name)
+        .ok_or_else(|| TexrsError::InvalidChoice(format!("[thesis] profile = \"{}\"", name)))
+}
+
+/* -------------------------------------------------------------------- */
+/// The source of `frontmatter/metadata.tex`: the layout of the profile,
+/// & a `\Thesis...` macro for each detail the front matter shows.
+/* -------------------------------------------------------------------- */
+pub fn metadata(config: &ProjectConfig) -> Result<String, TexrsError> {
+    let document = config.get_document();
+    let thesis = config.get_thesis();
+    let profile = profile(&thesis.get_profile())?;
+    let paper = profile
+        .paper
+        .map(str::to_owned)
+        .unwrap_or_else(|| document.get_paper());
+    let advisor = thesis.get_advisor();
+    let advisor_line = if advisor.is_empty() {
+        String::new()
+    } else {
+        format!("Advisor: {}", escape_tex(&advisor))
+    };
+
+    let macros = [
+        (
+            "ThesisTitle",
+            escape_tex(&document.get_title().unwrap_or_else(|| config.get_name())),
+        ),
+        ("ThesisAuthor", escape_tex(&document.get_author())),
+        ("ThesisUniversity", escape_tex(&thesis.get_university())),
+        ("ThesisDepartment", escape_tex(&thesis.get_department())),
+        ("ThesisDegree", escape_tex(&thesis.get_degree())),
+        ("ThesisAdvisor", escape_tex(&advisor)),
+        ("ThesisAdvisorLine", advisor_line),
+        ("ThesisSubmissionDate", thesis.get_submission_date()),
+        ("ThesisDegreeStatement", profile.degree_statement.to_owned()),
+        ("ThesisDeclaration", profile.declaration.to_owned()),
+    ];
+
+    let mut source = String::from(
+        "% Generated by texrs from the [thesis] & [document] tables in\n\
+         % config.toml, & rewritten on every build. Edit those instead.\n",
+    );
+    source.push_str(&format!(
+        "\\geometry{{{}, {}}}\n\\setstretch{{{}}}\n",
+        paper, profile.margins, profile.spacing
+    ));
+    for (name, value) in macros {
+        source.push_str(&format!("\\newcommand{{\\{}}}{{{}}}\n", name, value));
+    }
+    Ok(source)
+}
+
+/* -------------------------------------------------------------------- */
+/// Writes the metadata of the thesis into `tex/`, leaving the file alone
+/// if it's already up to date so that `watch` doesn't rebuild for it.
+/// Returns the file's path, & whether it was written.
+///
+/// ## Usage
+///
+/// ```rust
+/// thesis::write_metadata(&config, &dirs.tex)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn write_metadata(
+    config: &ProjectConfig,
+    tex: &Path,
+) -> Result<(PathBuf, bool), Box<dyn Error>> {
+    let path = tex.join(METADATA_FILE);
+    let written = write_if_changed(&path, &metadata(config)?)?;
+    Ok((path, written))
+}
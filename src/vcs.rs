@@ -0,0 +1,170 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::clean::AUX_EXTENSIONS;
+use crate::config::*;
+use crate::init::IMAGE_EXTENSIONS;
+use crate::message::*;
+use crate::template::{render, Context};
+use crate::TexrsError;
+
+use std::error::Error;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+pub const GITIGNORE_FILE: &str = ".gitignore";
+pub const GITATTRIBUTES_FILE: &str = ".gitattributes";
+
+/// The placeholders a `[vcs]` commit message may use.
+const MESSAGE_PLACEHOLDERS: [&str; 3] = ["name", "title", "author"];
+
+/* -------------------------------------------------------------------- */
+/// The files a new project's repository starts with, as paths in the
+/// project & their contents: a `.gitignore` keeping out `target/` & any
+/// auxiliary files, & a `.gitattributes` for PDFs & images, each unless
+/// the `[vcs]` table turns it off.
+/* -------------------------------------------------------------------- */
+pub fn files(vcs: &VcsInfo) -> Vec<(&'static str, String)> {
+    let mut files = Vec::new();
+    if vcs.get_gitignore() {
+        let mut gitignore = String::from("# Build output, written by texrs.\n/target/\n");
+        gitignore.push_str("\n# Leftovers from building a document in place.\n");
+        for extension in AUX_EXTENSIONS {
+            gitignore.push_str(&format!("*.{}\n", extension));
+        }
+        files.push((GITIGNORE_FILE, gitignore));
+    }
+
+    let attribute = match vcs.get_attributes() {
+        Attributes::Binary => Some("binary"),
+        Attributes::Lfs => Some("filter=lfs diff=lfs merge=lfs -text"),
+        Attributes::None => None,
+    };
+    if let Some(attribute) = attribute {
+        let gitattributes: String = IMAGE_EXTENSIONS
+            .iter()
+            .map(|extension| format!("*.{} {}\n", extension, attribute))
+            .collect();
+        files.push((GITATTRIBUTES_FILE, gitattributes));
+    }
+    files
+}
+
+/// The first commit's message, from the `[vcs]` table with its
+/// placeholders filled in, unescaped, from the config.
+pub fn commit_message(config: &ProjectConfig) -> Result<String, TexrsError> {
+    let document = config.get_document();
+    let mut context = Context::new();
+    context.insert("name".to_owned(), config.get_name());
+    context.insert(
+        "title".to_owned(),
+        document.get_title().unwrap_or_else(|| config.get_name()),
+    );
+    context.insert("author".to_owned(), document.get_author());
+    render(
+        &config.get_vcs().get_message(),
+        &MESSAGE_PLACEHOLDERS,
+        &context,
+    )
+    .map_err(|err| match err {
+        TexrsError::TemplateError(msg) => {
+            TexrsError::TemplateError(format!("[vcs] message: {}", msg))
+        }
+        err => err,
+    })
+}
+
+/* -------------------------------------------------------------------- */
+/// Makes the project staged in `dir` a git repository as `vcs` says:
+/// `git init`, on its initial branch if it names one, then the identity
+/// overrides, then a first commit of everything with `message`. Returns
+/// the events to report once the project is in place. A failed step
+/// stops the rest & is returned as `TexrsError::GitFailed`, with git's
+/// error output, for the caller to roll the project back.
+///
+/// ## Usage
+///
+/// ```rust
+/// let events = vcs::init_repository(&stage, &config.get_vcs(), &message)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn init_repository(
+    dir: &Path,
+    vcs: &VcsInfo,
+    message: &str,
+) -> Result<Vec<Event>, Box<dyn Error>> {
+    let branch = vcs
+        .get_branch()
+        .map(|branch| format!("--initial-branch={}", branch));
+    let mut init = vec!["init"];
+    init.extend(branch.as_deref());
+
+    let user_name = vcs.get_user_name();
+    let user_email = vcs.get_user_email();
+    let mut identity: Vec<Vec<&str>> = Vec::new();
+    if let Some(name) = &user_name {
+        identity.push(vec!["config", "user.name", name]);
+    }
+    if let Some(email) = &user_email {
+        identity.push(vec!["config", "user.email", email]);
+    }
+    let identity: Vec<&[&str]> = identity.iter().map(Vec::as_slice).collect();
+
+    let commit = ["commit", "-m", message];
+    let steps: [(&str, Vec<&[&str]>); 4] = [
+        ("init", vec![&init]),
+        ("identity", identity),
+        ("add", vec![&["add", "."]]),
+        ("commit", vec![&commit]),
+    ];
+    let mut events = Vec::new();
+    for (step, commands) in steps {
+        if commands.is_empty() {
+            continue;
+        }
+        for args in commands {
+            run_git(step, args, dir)?;
+        }
+        events.push(Event::Git {
+            step: step.to_owned(),
+            success: true,
+        });
+    }
+    Ok(events)
+}
+
+/// Runs git inside `dir`. A failure carries the step it was part of &
+/// git's error output.
+fn run_git(step: &str, args: &[&str], dir: &Path) -> Result<(), TexrsError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => TexrsError::DriverNotInstalled("git".to_owned()),
+            _ => TexrsError::IoError(err),
+        })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(TexrsError::GitFailed {
+            step: step.to_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
@@ -0,0 +1,163 @@
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | This file is part of texrs.                                                                                                       |
+// |                                                                                                                                   |
+// | texrs is free software: you can redistribute it and/or modify it under the terms                                                  |
+// | of the GNU General Public License as published by the Free Software Foundation,                                                   |
+// | either version 3 of the License, or (at your option) any later version.                                                           |
+// |                                                                                                                                   |
+// | texrs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;                                                |
+// | without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.                                         |
+// | See the GNU General Public License for more details.                                                                              |
+// |                                                                                                                                   |
+// | You should have received a copy of the GNU General Public License along with texrs. If not, see <https://www.gnu.org/licenses/>.  |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+// | Copyright (c) 2024 Ethan Barry <ethanbarry@howdytx.net>                                                                           |
+// | Feel free to contact the author if you do come across this source code for some reason...                                         |
+// | <https://github.com/ethanbarry> is the author's profile.                                                                          |
+// + - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +
+use crate::build::*;
+use crate::config::*;
+use crate::log::BuildLog;
+use crate::TexrsError;
+
+use colored::*;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the watched directories are scanned.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the sources must stay quiet before a rebuild starts, so that
+/// an editor saving several files at once only triggers one build.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// A snapshot of every watched file & when it was last modified.
+type Snapshot = BTreeMap<PathBuf, Option<SystemTime>>;
+
+/* -------------------------------------------------------------------- */
+/// Builds the project at `root`, then rebuilds it after every debounced change to
+/// its `tex/`, `bib/` or `graphics/` directories, wherever its layout
+/// puts them. A build still running
+/// when a new change lands is cancelled in favor of a fresh one. Runs
+/// until the process is interrupted.
+///
+/// ## Usage
+///
+/// ```rust
+/// watch_project(&root, config)?;
+/// ```
+/* -------------------------------------------------------------------- */
+pub fn watch_project(root: &Path, config: ProjectConfig) -> Result<(), Box<dyn Error>> {
+    let build_dirs = BuildDirs::new(root, &config)?;
+    let mut dirs: Vec<PathBuf> = [&build_dirs.tex, &build_dirs.bib, &build_dirs.graphics]
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .cloned()
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    // With the sources at the project root, the build's own output
+    // mustn't count as a change.
+    let ignored = [build_dirs.target.clone()];
+
+    let mut snapshot = scan(&dirs, &ignored);
+    let mut running = Some(start_build(root, &config));
+    let mut last_change: Option<Instant> = None;
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let current = scan(&dirs, &ignored);
+        if current != snapshot {
+            snapshot = current;
+            last_change = Some(Instant::now());
+        }
+
+        if let Some(changed_at) = last_change {
+            if changed_at.elapsed() >= DEBOUNCE {
+                last_change = None;
+                if let Some(build) = running.take() {
+                    build.cancel.store(true, Ordering::SeqCst);
+                    report(build.handle.join());
+                }
+                println!("[ {} ] Change detected; rebuilding.", "INFO".blue());
+                running = Some(start_build(root, &config));
+            }
+        }
+
+        if running.as_ref().is_some_and(|b| b.handle.is_finished()) {
+            if let Some(build) = running.take() {
+                report(build.handle.join());
+                println!("[ {} ] Watching for changes...", "INFO".blue());
+            }
+        }
+    }
+}
+
+/// A build on its own thread. It finishes with the parsed log, or with
+/// `Err(None)` if it was cancelled & `Err(Some(message))` if it failed.
+struct RunningBuild {
+    cancel: Arc<AtomicBool>,
+    handle: JoinHandle<Result<BuildLog, Option<String>>>,
+}
+
+fn start_build(root: &Path, config: &ProjectConfig) -> RunningBuild {
+    let options = BuildOptions::default();
+    let cancel = options.cancel.clone();
+    let root = root.to_path_buf();
+    let config = config.clone();
+    // `Box<dyn Error>` can't cross threads, so errors are flattened here.
+    let handle = thread::spawn(move || {
+        build_project(&root, config, &options).map_err(|err| {
+            match err.downcast_ref::<TexrsError>() {
+                Some(TexrsError::Cancelled) => None,
+                _ => Some(err.to_string()),
+            }
+        })
+    });
+    RunningBuild { cancel, handle }
+}
+
+fn report(result: thread::Result<Result<BuildLog, Option<String>>>) {
+    match result {
+        Ok(Ok(_)) => {}
+        Ok(Err(None)) => println!("[ {} ] {}", "WARN".yellow(), TexrsError::Cancelled),
+        Ok(Err(Some(err))) => println!("[ {} ] {}", "FAIL".red(), err),
+        Err(_) => println!("[ {} ] The build thread panicked.", "FAIL".red()),
+    }
+}
+
+/// Records the modification time of every file under `dirs`, except
+/// in hidden directories & those in `ignored`.
+fn scan(dirs: &[PathBuf], ignored: &[PathBuf]) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    let mut pending: Vec<PathBuf> = dirs.to_vec();
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.metadata() {
+                Ok(meta) if meta.is_dir() => {
+                    let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                    if !hidden && !ignored.contains(&path) {
+                        pending.push(path);
+                    }
+                }
+                Ok(meta) => {
+                    snapshot.insert(path, meta.modified().ok());
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    snapshot
+}